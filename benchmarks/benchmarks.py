@@ -3,6 +3,8 @@ import time
 from tabulate import tabulate
 
 
+# `@inject` handlers are native callables invoked through tp_call: PyO3 cannot give a class a
+# vectorcall slot, so every call still packs its arguments into a tuple.
 def bench_fastdi_simple(n: int) -> float:
     from typing import Annotated
 
@@ -32,10 +34,114 @@ def bench_fastdi_simple(n: int) -> float:
     return dt
 
 
+def bench_fastdi_partial(n: int) -> float:
+    from typing import Annotated
+
+    from fastdi import Container, Depends, inject, provide
+
+    c = Container()
+
+    @provide(c)
+    def v():
+        return 1
+
+    @provide(c)
+    def w():
+        return 2
+
+    @inject(c)
+    def handler(a: Annotated[int, Depends(v)], b: Annotated[int, Depends(w)]):
+        return a + b
+
+    handler(1)  # warmup/compile the plan for the missing parameter
+    t0 = time.perf_counter()
+    s = 0
+    for _ in range(n):
+        s += handler(1)
+    dt = time.perf_counter() - t0
+    assert s == n * 3
+    return dt
+
+
+def bench_python_wrapper(n: int) -> float:
+    """Baseline: a Python wrapper binding the signature on every call."""
+
+    import inspect
+    from functools import wraps
+    from typing import Annotated
+
+    from fastdi import Container, Depends, provide
+    from fastdi.types import extract_dep_params
+
+    c = Container()
+
+    @provide(c)
+    def v():
+        return 1
+
+    @provide(c)
+    def w():
+        return 2
+
+    def inject(func):
+        dep_params = extract_dep_params(func)
+        sig = inspect.signature(func)
+        plan = c._core.compile([key for _, key in dep_params])
+
+        @wraps(func)
+        def wrapper(*args, **kwargs):
+            bound = sig.bind_partial(*args, **kwargs)
+            missing = [name for name, _ in dep_params if name not in bound.arguments]
+            if missing:
+                for name, value in zip(missing, plan.execute(), strict=False):
+                    bound.arguments[name] = value
+            return func(*bound.args, **bound.kwargs)
+
+        return wrapper
+
+    @inject
+    def handler(a: Annotated[int, Depends(v)], b: Annotated[int, Depends(w)]):
+        return a + b
+
+    handler()  # warmup
+    t0 = time.perf_counter()
+    s = 0
+    for _ in range(n):
+        s += handler()
+    dt = time.perf_counter() - t0
+    assert s == n * 3
+    return dt
+
+
+def bench_transient_graph(n: int) -> float:
+    """Transient providers with one to three dependencies (provider call overhead)."""
+
+    from fastdi import Container
+
+    c = Container()
+    c.register("a", lambda: 1, singleton=False)
+    c.register("b", lambda a: a, singleton=False, dep_keys=["a"])
+    c.register("c", lambda a, b: a + b, singleton=False, dep_keys=["a", "b"])
+    c.register("d", lambda a, b, c: a + b + c, singleton=False, dep_keys=["a", "b", "c"])
+    plan = c._core.compile(["d"])
+
+    plan.execute()  # warmup
+    t0 = time.perf_counter()
+    s = 0
+    for _ in range(n):
+        s += plan.execute()[0]
+    dt = time.perf_counter() - t0
+    assert s == n * 4
+    return dt
+
+
 def run_all(n: int = 50000) -> None:
     rows = []
     for name, fn in [
         ("fastdi", bench_fastdi_simple),
+        ("fastdi (partial call)", bench_fastdi_partial),
+        ("python wrapper (baseline)", bench_python_wrapper),
+        ("fastdi transient graph", bench_transient_graph),
     ]:
         dt = fn(n)
         rows.append(
@@ -5,12 +5,32 @@ This module re-exports the main classes and decorators for convenience:
     from fastdi import Container, Depends, provide, inject, ainject
 """
 
-from .container import Container
+from .container import (
+    AsyncProviderInSyncContext,
+    CaptiveDependency,
+    CaptiveDependencyWarning,
+    Container,
+    DependencyCycle,
+    DisposeError,
+    FastDIError,
+    ProviderNotFound,
+    ResolutionError,
+    ScopeError,
+)
 from .decorators import ainject, ainject_method, inject, inject_method, provide
 from .types import Depends, make_key
 
 __all__ = [
     "Container",
+    "DisposeError",
+    "FastDIError",
+    "ProviderNotFound",
+    "DependencyCycle",
+    "AsyncProviderInSyncContext",
+    "ScopeError",
+    "ResolutionError",
+    "CaptiveDependency",
+    "CaptiveDependencyWarning",
     "Depends",
     "provide",
     "inject",
@@ -1,40 +1,70 @@
 """FastDI Container and execution utilities.
 
 This module wraps the PyO3 Rust core with a maintainable, typed Python API.
-It provides scopes, overrides, observability hooks, async resolution, and
-topological plan compilation for async execution.
+It provides scopes, overrides, observability hooks, and async resolution; the
+Rust core compiles and executes resolution plans for both sync and async code.
 """
 
 from __future__ import annotations
 
 import asyncio
-import contextvars
 import importlib
-import time
+import inspect
 import weakref
-from collections.abc import Callable, Iterable
+from collections.abc import Awaitable, Callable, Iterable
 from contextlib import contextmanager, suppress
-from dataclasses import dataclass
-from typing import Any, cast
-
-from .types import CoreContainerProto, Hook, Key, Scope, extract_dep_keys, make_key
+from typing import Any, Literal, cast
+
+from .types import (
+    CoreContainerProto,
+    CorePlanProto,
+    CoreScopeProto,
+    CoreValidationReportProto,
+    Hook,
+    Key,
+    Scope,
+    extract_dep_keys,
+    make_key,
+)
 
 _core = importlib.import_module("_fastdi_core")
 
-
-@dataclass(frozen=True)
-class _Plan:
-    """Compiled resolution plan for a set of roots.
-
-    Attributes:
-        order: Topologically sorted keys; dependencies appear before dependents.
-        deps: Mapping of key -> list of dependency keys.
-        has_async: Whether any provider in the plan is async.
-    """
-
-    order: list[Key]
-    deps: dict[Key, list[Key]]
-    has_async: bool
+# Exceptions raised by the core. Each derives from ``FastDIError`` and from the
+# builtin it replaces; resolution errors carry ``key`` and the dependency
+# ``path`` from the resolved root.
+
+#: Base class of FastDI errors.
+FastDIError: type[Exception] = _core.FastDIError
+#: No provider is registered for a key (also a ``KeyError``).
+ProviderNotFound: type[KeyError] = _core.ProviderNotFound
+#: Providers depend on each other in a cycle (also a ``RuntimeError``).
+DependencyCycle: type[RuntimeError] = _core.DependencyCycle
+#: An async provider was reached by sync resolution (also a ``RuntimeError``).
+AsyncProviderInSyncContext: type[RuntimeError] = _core.AsyncProviderInSyncContext
+#: A request scope is missing, closed or foreign (also a ``RuntimeError``).
+ScopeError: type[RuntimeError] = _core.ScopeError
+#: A singleton depends on a shorter-lived provider (``captive_dependencies="error"``).
+CaptiveDependency: type[RuntimeError] = _core.CaptiveDependency
+#: Warning category for captive dependencies (``captive_dependencies="warn"``).
+CaptiveDependencyWarning: type[UserWarning] = _core.CaptiveDependencyWarning
+#: A provider failed (with ``wrap_errors=True``); the original exception is the ``__cause__``.
+ResolutionError: type[RuntimeError] = _core.ResolutionError
+#: Raised when disposing of values fails; ``errors`` lists every failure.
+DisposeError: type[RuntimeError] = _core.DisposeError
+
+
+def _provider_kind(func: Callable[..., Any], context_manager: bool, async_context_manager: bool) -> str:
+    """Return the core provider kind for ``func``."""
+
+    if inspect.isasyncgenfunction(func):
+        return "async_generator"
+    if inspect.isgeneratorfunction(func):
+        return "generator"
+    if async_context_manager:
+        if asyncio.iscoroutinefunction(func):
+            raise TypeError("async context-manager providers must be plain functions returning the context manager")
+        return "async_context_manager"
+    return "context_manager" if context_manager else "value"
 
 
 class Container:
@@ -43,32 +73,45 @@ class Container:
     Provides registration, overrides, scopes, hooks, and resolution helpers.
     The heavy lifting (caching, provider invocation, overrides storage) happens
     in the Rust core. This Python wrapper adds ergonomics and async tooling.
+
+    Args:
+        wrap_errors: Raise exceptions from providers as `ResolutionError`,
+            carrying the failing ``key`` and the dependency ``path`` from the
+            resolved key, with the original exception as ``__cause__``.
+        captive_dependencies: What to do when a compiled plan has a singleton
+            depending on a request-scoped or transient provider, whose first
+            value would live as long as the singleton: ``"warn"`` (emit a
+            `CaptiveDependencyWarning` once per edge), ``"error"`` (raise
+            `CaptiveDependency`) or ``"ignore"``.
+        clock: Time source for singleton ``ttl`` expiry, a callable returning
+            seconds as a float; defaults to a monotonic clock. Tests can pass a
+            fake clock to expire values without waiting.
     """
 
-    def __init__(self) -> None:
+    def __init__(
+        self,
+        *,
+        wrap_errors: bool = False,
+        captive_dependencies: Literal["warn", "error", "ignore"] = "warn",
+        clock: Callable[[], float] | None = None,
+    ) -> None:
         # Typed reference to the PyO3 core container.
-        self._core: CoreContainerProto = cast(CoreContainerProto, _core.Container())
-
-        # Request-scope cache per asyncio Task; GC-friendly via WeakKeyDictionary.
-        self._task_caches: weakref.WeakKeyDictionary[asyncio.Task, dict[str, Any]] = weakref.WeakKeyDictionary()
-        # Fallback ContextVar for non-async contexts.
-        self._fallback_cache: contextvars.ContextVar[dict[str, Any]] = contextvars.ContextVar(
-            "fastdi_request_cache_fallback"
+        self._core: CoreContainerProto = cast(
+            CoreContainerProto, _core.Container(wrap_errors, captive_dependencies, clock)
         )
 
-        # Python-managed scope registry: key -> scope
-        self._scopes: dict[Key, Scope] = {}
+        # Implicit request scope per asyncio Task, used by async resolution when
+        # no scope was entered explicitly; GC-friendly via WeakKeyDictionary.
+        self._task_scopes: weakref.WeakKeyDictionary[asyncio.Task, CoreScopeProto] = weakref.WeakKeyDictionary()
 
-        # Observability hooks
-        self._hooks: list[Hook] = []
+        # Pending async teardown of finished tasks' implicit scopes
+        self._closing: set[asyncio.Future[Any]] = set()
 
-        # Container epoch: increments on graph mutations (register/override).
-        self._epoch: int = 0
+        # Plans of async resolution by requested keys; they follow wiring changes
+        self._async_plans: dict[tuple[Key, ...], CorePlanProto] = {}
 
-    # ---- Epoch management ------------------------------------------------------
-    def _bump_epoch(self) -> None:
-        """Increment the container epoch to invalidate compiled plans."""
-        self._epoch += 1
+        # Observability hooks
+        self._hooks: list[Hook] = []
 
     # ---- Hooks -----------------------------------------------------------------
     def add_hook(self, hook: Hook) -> None:
@@ -79,6 +122,7 @@ class Container:
         """
 
         self._hooks.append(hook)
+        self._core.set_hook(self._emit)
 
     def remove_hook(self, hook: Hook) -> None:
         """Unregister a previously added hook.
@@ -88,6 +132,8 @@ class Container:
 
         with suppress(ValueError):
             self._hooks.remove(hook)
+        if not self._hooks:
+            self._core.set_hook(None)
 
     def _emit(self, event: str, payload: dict[str, Any]) -> None:
         for h in list(self._hooks):
@@ -104,54 +150,440 @@ class Container:
         singleton: bool,
         dep_keys: list[Key] | None = None,
         scope: Scope | None = None,
+        context_manager: bool = False,
+        async_context_manager: bool = False,
+        dispose: Callable[[Any], Any] | None = None,
+        ttl: float | None = None,
+        expire_dependents: bool = False,
+        eager: bool = False,
     ) -> None:
         """Register a provider function under a given key.
 
+        Generator and async generator functions are injected with their first
+        yielded value; the code after ``yield`` runs as teardown when the owning
+        scope closes (the request scope, or the container/override layer for
+        singletons). Async teardown is awaited by ``Scope.aclose()`` /
+        ``async with`` and `aclose()`.
+
         Args:
             key: Unique provider identifier.
             func: Provider callable (sync or async).
             singleton: Cache result globally in Rust if True.
             dep_keys: Optional explicit dependency keys; inferred from Annotated
                 parameter metadata otherwise.
-            scope: Python-side scope: ``"transient"``, ``"request"``, or ``"singleton"``.
+            scope: ``"transient"``, ``"request"``, or ``"singleton"``.
+            context_manager: ``func`` returns a context manager; inject the
+                result of ``__enter__`` and call ``__exit__`` on teardown.
+            async_context_manager: ``func`` returns an async context manager;
+                inject the result of ``__aenter__`` and await ``__aexit__`` on
+                teardown. Requires async resolution.
+            dispose: Called with each produced value on teardown, e.g.
+                ``dispose=lambda pool: pool.close()``; may be async.
+            ttl: Singletons only: seconds after which the cached value expires
+                and is rebuilt on the next resolution, e.g. for credentials.
+                Time is read from the container's ``clock``.
+            expire_dependents: Singletons built from this provider's value
+                expire with it.
+            eager: Singletons only: build the value at startup with
+                `warmup()` / `awarmup()` instead of on first resolution.
+
+        Registering an existing key replaces its provider; see `replace()`.
+        """
+
+        self._register(
+            key,
+            func,
+            singleton=singleton,
+            dep_keys=dep_keys,
+            scope=scope,
+            context_manager=context_manager,
+            async_context_manager=async_context_manager,
+            dispose=dispose,
+            ttl=ttl,
+            expire_dependents=expire_dependents,
+            eager=eager,
+        )
+
+    def replace(
+        self,
+        key: Key,
+        func: Callable[..., Any],
+        *,
+        singleton: bool,
+        dep_keys: list[Key] | None = None,
+        scope: Scope | None = None,
+        context_manager: bool = False,
+        async_context_manager: bool = False,
+        dispose: Callable[[Any], Any] | None = None,
+        ttl: float | None = None,
+        expire_dependents: bool = False,
+        eager: bool = False,
+    ) -> None:
+        """Replace the provider registered under ``key``.
+
+        Takes the same arguments as `register()`. The value cached from the old
+        provider and every singleton transitively depending on ``key`` (in the
+        base registrations and override layers) are torn down, so nothing built
+        from the old provider is injected again; async teardown is deferred to
+        `aclose()`.
+
+        Raises:
+            ProviderNotFound: If no provider is registered for the key.
         """
 
+        self._register(
+            key,
+            func,
+            singleton=singleton,
+            dep_keys=dep_keys,
+            scope=scope,
+            context_manager=context_manager,
+            async_context_manager=async_context_manager,
+            dispose=dispose,
+            ttl=ttl,
+            expire_dependents=expire_dependents,
+            eager=eager,
+            replace=True,
+        )
+
+    def unregister(self, key: Key | Callable[..., Any]) -> None:
+        """Remove the provider registered under ``key``.
+
+        Its cached value and the singletons depending on it are torn down like
+        on `replace()`. Override layers are left alone.
+
+        Raises:
+            ProviderNotFound: If no provider is registered for the key.
+        """
+
+        self._core.unregister(make_key(key))
+
+    def reset(self, key: Key | Callable[..., Any], *, cascade: bool = False, dispose: bool = True) -> None:
+        """Clear the cached singleton of ``key`` so the next resolution rebuilds it.
+
+        Args:
+            key: Provider key or callable; its cached values in the base
+                registrations and every override layer are cleared.
+            cascade: Also clear the singletons transitively depending on ``key``.
+            dispose: Run the teardown of cleared values (async teardown is
+                deferred to `aclose()`); if False they are just dropped.
+
+        Raises:
+            ProviderNotFound: If no provider is registered for the key.
+        """
+
+        self._core.reset(make_key(key), cascade, dispose)
+
+    def reset_all(self, *, dispose: bool = True) -> None:
+        """Clear every cached singleton, base and override layers alike.
+
+        Unlike `close()`, the container stays usable. Cleared values are torn
+        down as by `reset()` unless ``dispose`` is False.
+        """
+
+        self._core.reset_all(dispose)
+
+    def _register(
+        self,
+        key: Key,
+        func: Callable[..., Any],
+        *,
+        singleton: bool,
+        dep_keys: list[Key] | None,
+        scope: Scope | None,
+        context_manager: bool,
+        async_context_manager: bool,
+        dispose: Callable[[Any], Any] | None,
+        ttl: float | None,
+        expire_dependents: bool,
+        eager: bool,
+        replace: bool = False,
+    ) -> None:
         if dep_keys is None:
             dep_keys = extract_dep_keys(func)
         is_async = asyncio.iscoroutinefunction(func)
-        self._core.register_provider(key, func, bool(singleton), bool(is_async), list(dep_keys))
-        self._scopes[key] = "singleton" if singleton else (scope or "transient")
-        self._bump_epoch()
+        kind = _provider_kind(func, context_manager, async_context_manager)
+        self._core.register_provider(
+            key,
+            func,
+            bool(singleton),
+            bool(is_async),
+            list(dep_keys),
+            scope,
+            kind,
+            dispose,
+            replace,
+            ttl,
+            expire_dependents,
+            eager,
+        )
 
     @contextmanager
-    def override(self, key_or_callable: Any, replacement: Callable[..., Any], *, singleton: bool = False):
+    def override(
+        self,
+        key_or_callable: Any,
+        replacement: Callable[..., Any],
+        *,
+        singleton: bool = False,
+        context_manager: bool = False,
+        async_context_manager: bool = False,
+        dispose: Callable[[Any], Any] | None = None,
+    ):
         """Temporarily override a provider within the context block.
 
+        Singletons created by the replacement are torn down when the block
+        exits; async teardown is deferred to `aclose()`.
+
         Args:
             key_or_callable: Provider key or original callable.
             replacement: Replacement provider (sync or async).
             singleton: Treat replacement as a singleton in Rust cache.
+            context_manager: ``replacement`` returns a context manager.
+            async_context_manager: ``replacement`` returns an async context manager.
+            dispose: Called with each value of ``replacement`` on teardown.
         """
 
         key = make_key(key_or_callable)
         dep_keys = extract_dep_keys(replacement)
         self._core.begin_override_layer()
-        self._bump_epoch()
         try:
             is_async = asyncio.iscoroutinefunction(replacement)
-            self._core.set_override(key, replacement, bool(singleton), bool(is_async), dep_keys)
+            kind = _provider_kind(replacement, context_manager, async_context_manager)
+            self._core.set_override(
+                key, replacement, bool(singleton), bool(is_async), dep_keys, None, kind, dispose
+            )
             yield
         finally:
             self._core.end_override_layer()
-            self._bump_epoch()
+
+    # ---- Freezing --------------------------------------------------------------
+    def freeze(self) -> None:
+        """Make the registrations immutable for faster, lock-free resolution.
+
+        Call it once the graph is complete, e.g. after startup wiring. Every
+        dependency must have a provider. Afterwards `register()`, `replace()`,
+        `unregister()`, `override()` and `reset()` raise ``RuntimeError``; use
+        `testing_copy()` to override providers in tests. Freezing twice is a
+        no-op.
+
+        Raises:
+            ProviderNotFound: If a dependency has no provider.
+            RuntimeError: If an override is active.
+        """
+
+        self._core.freeze()
+
+    @property
+    def frozen(self) -> bool:
+        """Whether `freeze()` was called."""
+
+        return self._core.frozen
+
+    def testing_copy(self) -> Container:
+        """Return an unfrozen container with the same registrations and hooks.
+
+        Caches start empty, so values are built anew. Meant for tests of a
+        frozen container: override providers on the copy and resolve (or
+        ``@inject``) through it.
+        """
+
+        copy = type(self)()
+        copy._core = self._core.testing_copy()
+        for hook in self._hooks:
+            copy.add_hook(hook)
+        return copy
+
+    # ---- Scopes ----------------------------------------------------------------
+    def request_scope(self) -> CoreScopeProto:
+        """Create a request scope handle.
+
+        Request-scoped providers are cached in the scope that is active when
+        they are resolved, by sync and async resolution alike. Enter the handle
+        as a context manager to activate it for the current thread or task; on
+        exit the teardown of its generator/context-manager values runs and its
+        cached values are dropped::
+
+            with container.request_scope():
+                handle_request()
+
+        Use ``async with`` when async generator or async context-manager
+        providers are resolved in the scope, so their teardown is awaited.
+        """
+
+        return self._core.new_scope()
+
+    def _implicit_scope(self) -> CoreScopeProto | None:
+        """Return the per-task scope used by async resolution outside explicit scopes.
+
+        The scope is closed when the task finishes; async teardown then runs in
+        a separate task.
+        """
+
+        task = asyncio.current_task()
+        if task is None:
+            return None
+        scope = self._task_scopes.get(task)
+        if scope is None:
+            scope = self._core.new_scope()
+            self._task_scopes[task] = scope
+            task.add_done_callback(lambda _task: self._close_task_scope(scope))
+        return scope
+
+    def _close_task_scope(self, scope: CoreScopeProto) -> None:
+        if not scope.has_async_teardown:
+            scope.close()
+            return
+        closing = asyncio.ensure_future(scope.aclose())
+        self._closing.add(closing)
+        closing.add_done_callback(self._closing.discard)
+
+    # ---- Validation ------------------------------------------------------------
+    def validate(self, sync_roots: Iterable[Key] = ()) -> CoreValidationReportProto:
+        """Check the whole dependency graph and report every problem at once.
+
+        Every provider of the base registrations and active override layers is
+        checked for missing dependencies, dependency cycles, and captive
+        dependencies (singletons depending on request-scoped or transient
+        providers, unless ``captive_dependencies="ignore"``). Keys injected by ``@inject`` /
+        ``@inject_method`` (and ``sync_roots``) are also checked for reachable
+        async providers. Useful at startup or in a test::
+
+            report = container.validate()
+            assert report.ok, str(report)
+
+        Returns:
+            A report whose ``problems`` each have ``kind`` (``"missing"``,
+            ``"cycle"``, ``"async_in_sync"`` or ``"scope"``), ``key``,
+            ``path`` and ``message``.
+        """
+
+        return self._core.validate(list(sync_roots))
+
+    def export_graph(self, format: Literal["mermaid", "dot", "json"] = "mermaid") -> str:
+        """Render the active dependency graph as text.
+
+        Args:
+            format: ``"mermaid"`` for a ``flowchart TD`` diagram, ``"dot"`` for
+                Graphviz, or ``"json"`` for a ``{"nodes": [...], "edges": [...]}``
+                document.
+
+        Every registered key is a node carrying its scope, whether it is async,
+        whether an override layer currently shadows it, and whether a cached
+        value exists; dependencies without a provider appear as missing nodes.
+        Edges point from a dependency to its dependent.
+        """
+
+        return self._core.export_graph(format)
+
+    # ---- Introspection ---------------------------------------------------------
+    def keys(self) -> list[Key]:
+        """Return every registered key (base registrations and override layers), sorted."""
+
+        return self._core.keys()
+
+    def dependencies(self, key: Key | Callable[..., Any], *, transitive: bool = False) -> list[Key]:
+        """Return the keys ``key`` depends on.
+
+        Direct dependencies come in declaration order; with ``transitive=True``
+        every key reachable from ``key`` is returned, sorted, leaving out
+        ``key`` itself even when it is part of a cycle. Dependencies without a
+        provider are included.
+
+        Raises:
+            ProviderNotFound: If no provider is registered for the key.
+        """
+
+        return self._core.dependencies(make_key(key), transitive)
+
+    def dependents(self, key: Key | Callable[..., Any], *, transitive: bool = False) -> list[Key]:
+        """Return the keys depending on ``key``, sorted.
+
+        With ``transitive=True`` this is everything affected by a change to
+        ``key``'s provider.
+        """
+
+        return self._core.dependents(make_key(key), transitive)
+
+    def provider_layer(self, key: Key | Callable[..., Any]) -> int:
+        """Return which entry currently provides ``key``.
+
+        ``0`` means the base registration; ``n`` means the ``n``-th active
+        override layer (``1`` for the outermost ``override()`` block).
+
+        Raises:
+            ProviderNotFound: If no provider is registered for the key.
+        """
+
+        return self._core.provider_layer(make_key(key))
+
+    # ---- Shutdown --------------------------------------------------------------
+    @property
+    def closed(self) -> bool:
+        """Whether the container was closed; closed containers reject resolution."""
+
+        return self._core.closed
+
+    def close(self) -> None:
+        """Dispose of cached singletons and close the container.
+
+        Singletons of base registrations and override layers alike are disposed
+        of in reverse creation order: by their provider's teardown (generator,
+        context manager, or ``dispose`` callback), else by calling their
+        ``close()`` method. Every value is disposed of even if some fail.
+
+        Raises:
+            DisposeError: If disposing of one or more values failed; its
+                ``errors`` attribute lists the exceptions.
+            RuntimeError: If some teardown must be awaited; use `aclose()`.
+        """
+
+        self._core.close()
+
+    async def aclose(self) -> None:
+        """Close the container, awaiting async teardown.
+
+        Like `close()`, but also awaits async generator and async
+        context-manager singletons (including those of exited override blocks)
+        and async ``dispose`` callbacks, and prefers a value's ``aclose()``
+        method over ``close()``.
+        """
+
+        await self._core.aclose()
+
+    # ---- Warm-up -----------------------------------------------------------------
+    def warmup(self, keys: Iterable[Key | Callable[..., Any]] | None = None) -> None:
+        """Instantiate singletons now instead of on first resolution.
+
+        Builds the singletons ``keys`` (by default every provider registered
+        with ``eager=True``) and their dependencies in dependency order, so pool
+        construction and configuration errors happen at startup.
+
+        Raises:
+            ProviderNotFound: If a key or dependency has no provider.
+            AsyncProviderInSyncContext: If an async provider is reached; use
+                `awarmup()`.
+            ValueError: If a key is not a singleton.
+        """
+
+        self._core.warmup(None if keys is None else [make_key(k) for k in keys])
+
+    async def awarmup(self, keys: Iterable[Key | Callable[..., Any]] | None = None) -> None:
+        """Async counterpart of `warmup()`; supports async providers.
+
+        Independent async singletons are started concurrently.
+        """
+
+        await self._core.awarmup(None if keys is None else [make_key(k) for k in keys])
 
     # ---- Sync resolution (Rust core) -------------------------------------------
     def resolve(self, key: Key) -> Any:
         """Resolve a single key synchronously via the Rust core.
 
         Raises:
-            KeyError: If no provider is registered for the key.
-            RuntimeError: On dependency cycles or invalid graph for sync.
+            ProviderNotFound: If no provider is registered for the key or one
+                of its dependencies (a ``KeyError``).
+            DependencyCycle: On dependency cycles.
+            AsyncProviderInSyncContext: If an async provider is reached.
         """
 
         return self._core.resolve(key)
@@ -161,169 +593,31 @@ class Container:
 
         return list(self._core.resolve_many(list(keys)))
 
-    # ---- Async resolution (Python executor) ------------------------------------
+    # ---- Async resolution (Rust core) ------------------------------------------
     async def resolve_async(self, key: Key) -> Any:
         """Resolve a single key in async mode.
 
-        This path supports async providers, request scope, and hooks.
+        This path supports async providers, request scope, and hooks. Outside an
+        explicitly entered scope, request-scoped values are cached per task.
         """
 
-        seen: set = set()
-        return await self._resolve_key_async(key, seen)
+        values = await self._aresolve((key,))
+        return values[0]
 
     async def resolve_many_async(self, keys: Iterable[Key]) -> list[Any]:
         """Resolve multiple keys in async mode."""
 
-        seen: set = set()
-        return [await self._resolve_key_async(k, seen) for k in keys]
-
-    def _get_or_create_request_cache(self) -> dict[str, Any]:
-        task = asyncio.current_task()
-        if task is not None:
-            cache = self._task_caches.get(task)
-            if cache is None:
-                cache = {}
-                self._task_caches[task] = cache
-            return cache
-        try:
-            return self._fallback_cache.get()
-        except LookupError:
-            cache = {}
-            self._fallback_cache.set(cache)
-            return cache
-
-    async def _resolve_key_async(self, key: Key, seen: set) -> Any:
-        if key in seen:
-            raise RuntimeError(f"Dependency cycle detected at key: {key}")
-        seen.add(key)
-
-        # Request-scope cache lookup
-        scope = self._scopes.get(key, "transient")
-        if scope == "request":
-            cache = self._get_or_create_request_cache()
-            if key in cache:
-                seen.remove(key)
-                return cache[key]
-
-        callable_obj, singleton, is_async, dep_keys = self._core.get_provider_info(key)
-
-        # Singleton cache check (Rust-managed)
-        if singleton:
-            cached = self._core.get_cached(key)
-            if cached is not None:
-                seen.remove(key)
-                return cached
-
-        # Resolve dependencies first
-        args = [await self._resolve_key_async(dep, seen) for dep in dep_keys]
-
-        # Call provider
-        start = time.perf_counter()
-        self._emit("provider_start", {"key": key, "async": is_async})
-        res = callable_obj(*args)
-        if is_async:
-            res = await res
-        self._emit(
-            "provider_end",
-            {"key": key, "async": is_async, "duration_s": time.perf_counter() - start},
-        )
+        return list(await self._aresolve(tuple(keys)))
 
-        # Cache
-        if singleton:
-            self._core.set_cached(key, res)
-        elif scope == "request":
-            cache = self._get_or_create_request_cache()
-            cache[key] = res
+    def _aresolve(self, keys: tuple[Key, ...]) -> Awaitable[list[Any]]:
+        """Start async resolution of ``keys`` with the plan compiled for them.
 
-        seen.remove(key)
-        return res
-
-    # ---- Plan compilation (topological) ----------------------------------------
-    def _build_plan(self, root_keys: list[Key], *, allow_async: bool) -> _Plan:
-        """Compile a dependency plan for the given root keys.
-
-        Uses an iterative DFS to avoid Python recursion limits on deep graphs.
-        Returns a topologically sorted order and dependency map. Fails early on
-        cycles or the presence of async providers in a sync-only plan.
-        """
-
-        deps: dict[Key, list[Key]] = {}
-        state: dict[Key, int] = {}
-        order: list[Key] = []
-        has_async = False
-
-        for root in root_keys:
-            if state.get(root, 0) == 2:
-                continue
-            stack: list[tuple[Key, int]] = [(root, 0)]  # (key, phase 0=enter,1=exit)
-            while stack:
-                k, phase = stack.pop()
-                st = state.get(k, 0)
-                if phase == 0:
-                    if st == 1:
-                        raise RuntimeError(f"Dependency cycle detected at key: {k}")
-                    if st == 2:
-                        continue
-                    state[k] = 1
-                    _, _, is_async, dep_keys = self._core.get_provider_info(k)
-                    dlist = list(dep_keys)
-                    deps[k] = dlist
-                    if is_async:
-                        has_async = True
-                    stack.append((k, 1))
-                    for d in reversed(dlist):
-                        if state.get(d, 0) != 2:
-                            stack.append((d, 0))
-                else:
-                    state[k] = 2
-                    order.append(k)
-
-        if not allow_async and has_async:
-            raise RuntimeError("Async provider found in sync plan; use @ainject")
-        return _Plan(order=order, deps=deps, has_async=has_async)
-
-    async def _run_plan_async(self, plan: _Plan) -> dict[Key, Any]:
-        """Execute a compiled plan in async mode.
-
-        Computes each node once, honoring singleton/request caches and emitting
-        observability events.
+        The task's implicit scope is only created when the plan has
+        request-scoped providers or teardown to own.
         """
 
-        computed: dict[Key, Any] = {}
-        for key in plan.order:
-            scope = self._scopes.get(key, "transient")
-            if scope == "request":
-                cache = self._get_or_create_request_cache()
-                if key in cache:
-                    self._emit("cache_hit", {"key": key, "scope": "request"})
-                    computed[key] = cache[key]
-                    continue
-
-            callable_obj, singleton, is_async, _ = self._core.get_provider_info(key)
-            if singleton:
-                cached = self._core.get_cached(key)
-                if cached is not None:
-                    self._emit("cache_hit", {"key": key, "scope": "singleton"})
-                    computed[key] = cached
-                    continue
-
-            args = [computed[d] for d in plan.deps.get(key, [])]
-            start = time.perf_counter()
-            self._emit("provider_start", {"key": key, "async": is_async})
-            res = callable_obj(*args)
-            if is_async:
-                res = await res
-            self._emit(
-                "provider_end",
-                {"key": key, "async": is_async, "duration_s": time.perf_counter() - start},
-            )
-
-            if singleton:
-                self._core.set_cached(key, res)
-            elif scope == "request":
-                cache = self._get_or_create_request_cache()
-                cache[key] = res
-
-            computed[key] = res
-
-        return computed
+        plan = self._async_plans.get(keys)
+        if plan is None:
+            plan = self._async_plans[keys] = self._core.compile(list(keys), allow_async=True)
+        scope = self._implicit_scope() if plan.needs_scope else None
+        return plan.execute_async(scope)
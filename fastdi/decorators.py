@@ -8,39 +8,100 @@ from __future__ import annotations
 
 import inspect
 from collections.abc import Awaitable, Callable, Coroutine
-from functools import wraps
-from typing import Any, ParamSpec, TypeVar
+from functools import update_wrapper, wraps
+from typing import Any, ParamSpec, TypeVar, cast
 
 from .container import Container
-from .types import Key, Scope, extract_dep_keys, extract_dep_params, make_key
+from .types import CorePlanProto, Key, Scope, extract_dep_keys, extract_dep_params, extract_dep_slots, make_key
 
 P = ParamSpec("P")
 R = TypeVar("R")
 
 
+def _injector(container: Container, func: Callable[..., R]) -> Callable[..., R]:
+    """Wrap a sync function into a Rust injector resolving its dependencies.
+
+    The injector is created once per function: it keeps each dependency
+    parameter's position and the plan compiled for their keys, and on every
+    call resolves the parameters the caller did not pass.
+    """
+
+    injector = container._core.injector(func, extract_dep_slots(func))
+    update_wrapper(injector, func)
+    return cast("Callable[..., R]", injector)
+
+
+async def _aresolve_missing(
+    container: Container, plan: CorePlanProto, missing: list[tuple[str, Key]], total: int
+) -> list[Any]:
+    """Resolve values for parameters the caller did not pass explicitly.
+
+    The precompiled plan covers every dependency parameter, so it is used as-is
+    when nothing was passed; partial calls resolve only the missing keys.
+    Request-scoped values are cached in the scope entered by the caller, or in
+    the current task's implicit scope otherwise.
+    """
+
+    scope = container._implicit_scope() if plan.needs_scope else None
+    if len(missing) == total:
+        return await plan.execute_async(scope)
+    return await container._core.resolve_many_plan_async([key for _, key in missing], scope)
+
+
 def provide(
     container: Container,
     *,
     singleton: bool = False,
     key: Key | None = None,
     scope: Scope | None = None,
+    context_manager: bool = False,
+    async_context_manager: bool = False,
+    dispose: Callable[[Any], Any] | None = None,
+    ttl: float | None = None,
+    expire_dependents: bool = False,
+    eager: bool = False,
 ):
     """Register a function as a provider.
 
     The decorated function is returned unchanged, allowing direct invocation in
-    tests if desired.
+    tests if desired. Generator and async generator functions provide their
+    first yielded value and run the rest as teardown when the owning scope
+    closes.
 
     Args:
         container: Target DI container where the provider will be registered.
         singleton: Cache result globally (Rust cache) on first computation.
         key: Optional explicit registration key; by default derived from the function.
-        scope: Optional Python-managed scope ("transient" or "request").
+        scope: Optional scope ("transient" or "request"); request-scoped values
+            are cached in the active request scope.
+        context_manager: The function returns a context manager; the result of
+            ``__enter__`` is injected and ``__exit__`` runs on teardown.
+        async_context_manager: The function returns an async context manager
+            (``__aenter__``/``__aexit__``); requires async resolution.
+        dispose: Optional callback (sync or async) called with each produced
+            value on teardown.
+        ttl: Singletons only: seconds after which the cached value is rebuilt.
+        expire_dependents: Singletons built from this provider's value expire
+            with it.
+        eager: Singletons only: build the value in `Container.warmup()`.
     """
 
     def decorator(func: Callable[P, R]) -> Callable[P, R]:
         k = key or make_key(func)
         dep_keys = extract_dep_keys(func)
-        container.register(k, func, singleton=singleton, dep_keys=dep_keys, scope=scope)
+        container.register(
+            k,
+            func,
+            singleton=singleton,
+            dep_keys=dep_keys,
+            scope=scope,
+            context_manager=context_manager,
+            async_context_manager=async_context_manager,
+            dispose=dispose,
+            ttl=ttl,
+            expire_dependents=expire_dependents,
+            eager=eager,
+        )
         return func
 
     return decorator
@@ -50,40 +111,14 @@ def inject(container: Container):
     """Decorator for sync call sites.
 
     Compiles and validates a plan at decoration time and executes the call via
-    the Rust core resolver. The resulting wrapper preserves the original
-    signature, filling ``Annotated[..., Depends(...)]`` parameters when they
-    are not provided explicitly.
+    the Rust core plan executor; the plan is only recompiled when registrations
+    or overrides change. The resulting wrapper is a Rust injector object that
+    preserves the original signature, filling ``Annotated[..., Depends(...)]``
+    parameters when they are not provided explicitly.
     """
 
     def decorator(func: Callable[..., R]) -> Callable[..., R]:
-        dep_params = extract_dep_params(func)
-        dep_keys = [key for _, key in dep_params]
-        sig = inspect.signature(func)
-        # compile/validate now and capture epoch
-        plan = container._build_plan(dep_keys, allow_async=False)
-        validated_epoch = container._epoch
-
-        @wraps(func)
-        def wrapper(*args: Any, **kwargs: Any) -> R:
-            nonlocal validated_epoch, plan
-            # Re-validate on epoch change
-            if container._epoch != validated_epoch:
-                plan = container._build_plan(dep_keys, allow_async=False)
-                validated_epoch = container._epoch
-
-            if not dep_params:
-                return func(*args, **kwargs)
-
-            bound = sig.bind_partial(*args, **kwargs)
-            missing = [(name, key) for name, key in dep_params if name not in bound.arguments]
-            if missing:
-                keys_to_resolve = [key for _, key in missing]
-                resolved = container._core.resolve_many_plan(keys_to_resolve)
-                for (name, _), value in zip(missing, resolved, strict=False):
-                    bound.arguments[name] = value
-            return func(*bound.args, **bound.kwargs)
-
-        return wrapper
+        return _injector(container, func)
 
     return decorator
 
@@ -91,8 +126,8 @@ def inject(container: Container):
 def ainject(container: Container):
     """Decorator for async call sites.
 
-    Compiles a plan and executes it iteratively in topological order. The
-    resulting wrapper preserves the original signature, resolving
+    Compiles a plan at decoration time and executes it on the Rust async
+    executor. The resulting wrapper preserves the original signature, resolving
     ``Annotated[..., Depends(...)]`` parameters when missing before awaiting
     the original function.
     """
@@ -103,25 +138,19 @@ def ainject(container: Container):
         dep_params = extract_dep_params(func)
         dep_keys = [key for _, key in dep_params]
         sig = inspect.signature(func)
-        plan = container._build_plan(dep_keys, allow_async=True)
-        plan_epoch = container._epoch
+        plan = container._core.compile(dep_keys, allow_async=True)
 
         @wraps(func)
         async def wrapper(*args: Any, **kwargs: Any) -> R:
-            nonlocal plan, plan_epoch
-            if container._epoch != plan_epoch:
-                plan = container._build_plan(dep_keys, allow_async=True)
-                plan_epoch = container._epoch
-
             if not dep_params:
                 return await func(*args, **kwargs)
 
             bound = sig.bind_partial(*args, **kwargs)
             missing = [(name, key) for name, key in dep_params if name not in bound.arguments]
             if missing:
-                computed = await container._run_plan_async(plan)
-                for name, key in missing:
-                    bound.arguments[name] = computed[key]
+                resolved = await _aresolve_missing(container, plan, missing, len(dep_params))
+                for (name, _), value in zip(missing, resolved, strict=False):
+                    bound.arguments[name] = value
             return await func(*bound.args, **bound.kwargs)
 
         return wrapper
@@ -133,37 +162,12 @@ def inject_method(container: Container):
     """Decorator for sync instance methods that need injection.
 
     The resulting wrapper expects to be called as a bound method (i.e., with
-    ``self``). Dependencies declared with ``Depends`` are injected and passed
-    positionally after ``self``.
+    ``self``). Dependencies declared with ``Depends`` are injected when not
+    provided explicitly.
     """
 
     def decorator(func: Callable[..., R]) -> Callable[..., R]:
-        dep_params = extract_dep_params(func)
-        dep_keys = [key for _, key in dep_params]
-        sig = inspect.signature(func)
-        plan = container._build_plan(dep_keys, allow_async=False)
-        validated_epoch = container._epoch
-
-        @wraps(func)
-        def wrapper(self: Any, *args: Any, **kwargs: Any) -> R:
-            nonlocal plan, validated_epoch
-            if container._epoch != validated_epoch:
-                plan = container._build_plan(dep_keys, allow_async=False)
-                validated_epoch = container._epoch
-
-            if not dep_params:
-                return func(self, *args, **kwargs)
-
-            bound = sig.bind_partial(self, *args, **kwargs)
-            missing = [(name, key) for name, key in dep_params if name not in bound.arguments]
-            if missing:
-                keys_to_resolve = [key for _, key in missing]
-                resolved = container._core.resolve_many_plan(keys_to_resolve)
-                for (name, _), value in zip(missing, resolved, strict=False):
-                    bound.arguments[name] = value
-            return func(*bound.args, **bound.kwargs)
-
-        return wrapper
+        return _injector(container, func)
 
     return decorator
 
@@ -182,25 +186,19 @@ def ainject_method(container: Container):
         dep_params = extract_dep_params(func)
         dep_keys = [key for _, key in dep_params]
         sig = inspect.signature(func)
-        plan = container._build_plan(dep_keys, allow_async=True)
-        plan_epoch = container._epoch
+        plan = container._core.compile(dep_keys, allow_async=True)
 
         @wraps(func)
         async def wrapper(self: Any, *args: Any, **kwargs: Any) -> R:
-            nonlocal plan, plan_epoch
-            if container._epoch != plan_epoch:
-                plan = container._build_plan(dep_keys, allow_async=True)
-                plan_epoch = container._epoch
-
             if not dep_params:
                 return await func(self, *args, **kwargs)
 
             bound = sig.bind_partial(self, *args, **kwargs)
             missing = [(name, key) for name, key in dep_params if name not in bound.arguments]
             if missing:
-                computed = await container._run_plan_async(plan)
-                for name, key in missing:
-                    bound.arguments[name] = computed[key]
+                resolved = await _aresolve_missing(container, plan, missing, len(dep_params))
+                for (name, _), value in zip(missing, resolved, strict=False):
+                    bound.arguments[name] = value
             return await func(*bound.args, **bound.kwargs)
 
         return wrapper
@@ -7,7 +7,7 @@ dependency metadata from callables.
 from __future__ import annotations
 
 import inspect
-from collections.abc import Callable
+from collections.abc import Awaitable, Callable
 from typing import Annotated, Any, Protocol, get_args, get_origin
 
 # Public type aliases
@@ -16,12 +16,71 @@ Scope = str  # one of: "transient", "request", "singleton"
 Hook = Callable[[str, dict[str, Any]], None]
 
 
+class CorePlanProto(Protocol):
+    """Protocol describing the Rust core compiled plan (`_fastdi_core.Plan`)."""
+
+    @property
+    def keys(self) -> list[str]: ...
+    @property
+    def order(self) -> list[str]: ...
+    @property
+    def needs_scope(self) -> bool: ...
+    def execute(self) -> list[Any]: ...
+    def execute_async(self, default_scope: CoreScopeProto | None = None) -> Awaitable[list[Any]]: ...
+
+
+class CoreInjectorProto(Protocol):
+    """Protocol describing the Rust core injected call site (`_fastdi_core.Injector`)."""
+
+    def __call__(self, *args: Any, **kwargs: Any) -> Any: ...
+
+
+class CoreScopeProto(Protocol):
+    """Protocol describing the Rust core request scope handle (`_fastdi_core.Scope`)."""
+
+    @property
+    def closed(self) -> bool: ...
+    @property
+    def has_async_teardown(self) -> bool: ...
+    def close(self) -> None: ...
+    def aclose(self) -> Awaitable[None]: ...
+    def __enter__(self) -> CoreScopeProto: ...
+    def __exit__(self, *exc: Any) -> bool: ...
+    def __aenter__(self) -> Awaitable[CoreScopeProto]: ...
+    def __aexit__(self, *exc: Any) -> Awaitable[bool | None]: ...
+
+
+class CoreProblemProto(Protocol):
+    """Protocol describing one problem of a validation report (`_fastdi_core.Problem`)."""
+
+    @property
+    def kind(self) -> str: ...
+    @property
+    def key(self) -> str: ...
+    @property
+    def path(self) -> list[str]: ...
+    @property
+    def message(self) -> str: ...
+
+
+class CoreValidationReportProto(Protocol):
+    """Protocol describing the result of `Container.validate()` (`_fastdi_core.ValidationReport`)."""
+
+    @property
+    def ok(self) -> bool: ...
+    @property
+    def problems(self) -> list[CoreProblemProto]: ...
+    def __len__(self) -> int: ...
+
+
 class CoreContainerProto(Protocol):
     """Protocol describing the Rust core container interface.
 
     This enables static typing for the PyO3-backed `_fastdi_core.Container`.
     """
 
+    @property
+    def closed(self) -> bool: ...
     def register_provider(
         self,
         key: str,
@@ -29,10 +88,38 @@ class CoreContainerProto(Protocol):
         singleton: bool,
         is_async: bool,
         dep_keys: list[str],
+        scope: str | None = None,
+        kind: str | None = None,
+        disposer: Callable[[Any], Any] | None = None,
+        replace: bool = False,
+        ttl: float | None = None,
+        expire_dependents: bool = False,
+        eager: bool = False,
     ) -> None: ...
+    def unregister(self, key: str) -> None: ...
+    def reset(self, key: str, cascade: bool = False, dispose: bool = True) -> None: ...
+    def reset_all(self, dispose: bool = True) -> None: ...
     def resolve(self, key: str) -> Any: ...
     def resolve_many(self, keys: list[str]) -> list[Any]: ...
     def resolve_many_plan(self, keys: list[str]) -> list[Any]: ...
+    def compile(self, keys: list[str], allow_async: bool = False) -> CorePlanProto: ...
+    def injector(
+        self, target: Callable[..., Any], params: list[tuple[str, str, int | None, bool]]
+    ) -> CoreInjectorProto: ...
+    def resolve_async(self, key: str, default_scope: CoreScopeProto | None = None) -> Awaitable[Any]: ...
+    def resolve_many_plan_async(
+        self, keys: list[str], default_scope: CoreScopeProto | None = None
+    ) -> Awaitable[list[Any]]: ...
+    def freeze(self) -> None: ...
+    @property
+    def frozen(self) -> bool: ...
+    def testing_copy(self) -> CoreContainerProto: ...
+    def warmup(self, keys: list[str] | None = None) -> None: ...
+    def awarmup(self, keys: list[str] | None = None) -> Awaitable[list[Any]]: ...
+    def new_scope(self) -> CoreScopeProto: ...
+    def validate(self, sync_roots: list[str] | None = None) -> CoreValidationReportProto: ...
+    def export_graph(self, format: str = "mermaid") -> str: ...
+    def set_hook(self, hook: Hook | None) -> None: ...
     def begin_override_layer(self) -> None: ...
     def set_override(
         self,
@@ -41,9 +128,18 @@ class CoreContainerProto(Protocol):
         singleton: bool,
         is_async: bool,
         dep_keys: list[str],
+        scope: str | None = None,
+        kind: str | None = None,
+        disposer: Callable[[Any], Any] | None = None,
     ) -> None: ...
     def end_override_layer(self) -> None: ...
+    def close(self) -> None: ...
+    def aclose(self) -> Awaitable[None]: ...
     def get_provider_info(self, key: str) -> tuple[Callable[..., Any], bool, bool, list[str]]: ...
+    def keys(self) -> list[str]: ...
+    def dependencies(self, key: str, transitive: bool = False) -> list[str]: ...
+    def dependents(self, key: str, transitive: bool = False) -> list[str]: ...
+    def provider_layer(self, key: str) -> int: ...
     def get_cached(self, key: str) -> Any | None: ...
     def set_cached(self, key: str, value: Any) -> None: ...
 
@@ -106,6 +202,25 @@ def extract_dep_params(func: Callable[..., Any]) -> list[tuple[str, Key]]:
     return out
 
 
+def extract_dep_slots(func: Callable[..., Any]) -> list[tuple[str, Key, int | None, bool]]:
+    """Return ``(name, key, position, keyword)`` for each dependency parameter.
+
+    ``position`` is the parameter's positional index (``None`` if keyword-only)
+    and ``keyword`` whether it can be passed by keyword.
+    """
+
+    positional = (inspect.Parameter.POSITIONAL_ONLY, inspect.Parameter.POSITIONAL_OR_KEYWORD)
+    variadic = (inspect.Parameter.VAR_POSITIONAL, inspect.Parameter.VAR_KEYWORD)
+    out: list[tuple[str, Key, int | None, bool]] = []
+    for index, param in enumerate(inspect.signature(func).parameters.values()):
+        key = _extract_dep_key(param.annotation)
+        if key is None or param.kind in variadic:
+            continue
+        position = index if param.kind in positional else None
+        out.append((param.name, key, position, param.kind != inspect.Parameter.POSITIONAL_ONLY))
+    return out
+
+
 def extract_dep_keys(func: Callable[..., Any]) -> list[Key]:
     """Extract dependency keys from a callable's parameters."""
 
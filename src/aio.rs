@@ -0,0 +1,182 @@
+//! Minimal awaitable machinery used by the async resolution paths.
+//!
+//! Python drives an `await` by calling `send`/`throw` on the iterator returned
+//! from `__await__`. `Awaitable` implements that protocol on top of an
+//! [`AsyncTask`] state machine, which in turn forwards each step to whatever
+//! provider coroutine it is currently waiting on (`yield from` semantics).
+
+use pyo3::exceptions::{PyRuntimeError, PyStopIteration, PyTypeError};
+use pyo3::intern;
+use pyo3::prelude::*;
+use pyo3::types::PyType;
+
+/// Input delivered to a suspended task by the event loop.
+pub(crate) enum Resume {
+    Send(Py<PyAny>),
+    Throw(PyErr),
+}
+
+/// Outcome of a single step: either a value to yield to the event loop or the
+/// final result of the task.
+pub(crate) enum Poll {
+    Pending(Py<PyAny>),
+    Ready(Py<PyAny>),
+}
+
+pub(crate) trait AsyncTask: Send + Sync {
+    fn resume(&mut self, py: Python<'_>, input: Resume) -> PyResult<Poll>;
+
+    /// Release any inner awaitable when the outer one is closed early.
+    fn close(&mut self, _py: Python<'_>) {}
+}
+
+/// A task that completes on its first step, e.g. the result of `__aenter__`.
+pub(crate) struct Ready(Option<Py<PyAny>>);
+
+impl Ready {
+    pub(crate) fn new(value: Py<PyAny>) -> Self {
+        Self(Some(value))
+    }
+}
+
+impl AsyncTask for Ready {
+    fn resume(&mut self, py: Python<'_>, input: Resume) -> PyResult<Poll> {
+        if let Resume::Throw(err) = input {
+            return Err(err);
+        }
+        Ok(Poll::Ready(self.0.take().unwrap_or_else(|| py.None())))
+    }
+}
+
+/// An inner awaitable (e.g. a provider coroutine) being driven by a task.
+pub(crate) struct Inflight {
+    iter: Py<PyAny>,
+}
+
+impl Inflight {
+    pub(crate) fn start(awaitable: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let py = awaitable.py();
+        let await_fn = awaitable.getattr(intern!(py, "__await__")).map_err(|_| {
+            PyTypeError::new_err(format!(
+                "object {} can't be used in 'await' expression",
+                awaitable.get_type().name().map(|n| n.to_string()).unwrap_or_default()
+            ))
+        })?;
+        Ok(Self { iter: await_fn.call0()?.unbind() })
+    }
+
+    /// Advance the inner iterator the same way `yield from` would.
+    pub(crate) fn resume(&self, py: Python<'_>, input: Resume) -> PyResult<Poll> {
+        let it = self.iter.bind(py);
+        let step = match input {
+            Resume::Send(value) if value.is_none(py) => it.call_method0(intern!(py, "__next__")),
+            Resume::Send(value) => it.call_method1(intern!(py, "send"), (value,)),
+            Resume::Throw(err) => {
+                if it.hasattr(intern!(py, "throw"))? {
+                    it.call_method1(intern!(py, "throw"), (err.into_value(py),))
+                } else {
+                    return Err(err);
+                }
+            }
+        };
+        match step {
+            Ok(yielded) => Ok(Poll::Pending(yielded.unbind())),
+            Err(err) if err.is_instance_of::<PyStopIteration>(py) => {
+                let value = err.value(py).getattr(intern!(py, "value"))?;
+                Ok(Poll::Ready(value.unbind()))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    pub(crate) fn close(&self, py: Python<'_>) {
+        let it = self.iter.bind(py);
+        if let Ok(true) = it.hasattr(intern!(py, "close")) {
+            let _ = it.call_method0(intern!(py, "close"));
+        }
+    }
+}
+
+/// Python-visible awaitable wrapping an [`AsyncTask`].
+///
+/// The object is its own iterator; once the task completes or fails it cannot
+/// be awaited again.
+#[pyclass(module = "_fastdi_core")]
+pub(crate) struct Awaitable {
+    task: Option<Box<dyn AsyncTask>>,
+}
+
+impl Awaitable {
+    pub(crate) fn new(task: Box<dyn AsyncTask>) -> Self {
+        Self { task: Some(task) }
+    }
+
+    fn step(&mut self, py: Python<'_>, input: Resume) -> PyResult<Py<PyAny>> {
+        let task = match self.task.as_mut() {
+            Some(task) => task,
+            None => {
+                return match input {
+                    Resume::Throw(err) => Err(err),
+                    Resume::Send(_) => {
+                        Err(PyRuntimeError::new_err("cannot reuse already awaited resolution"))
+                    }
+                }
+            }
+        };
+        match task.resume(py, input) {
+            Ok(Poll::Pending(yielded)) => Ok(yielded),
+            Ok(Poll::Ready(value)) => {
+                self.task = None;
+                Err(PyStopIteration::new_err((value,)))
+            }
+            Err(err) => {
+                self.task = None;
+                Err(err)
+            }
+        }
+    }
+}
+
+#[pymethods]
+impl Awaitable {
+    fn __await__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        self.step(py, Resume::Send(py.None()))
+    }
+
+    fn send(&mut self, py: Python<'_>, value: Py<PyAny>) -> PyResult<Py<PyAny>> {
+        self.step(py, Resume::Send(value))
+    }
+
+    #[pyo3(signature = (typ, val=None, _tb=None))]
+    fn throw(
+        &mut self,
+        py: Python<'_>,
+        typ: Bound<'_, PyAny>,
+        val: Option<Bound<'_, PyAny>>,
+        _tb: Option<Bound<'_, PyAny>>,
+    ) -> PyResult<Py<PyAny>> {
+        let exc = if typ.is_instance_of::<PyType>() {
+            match val {
+                Some(v) if !v.is_none() => typ.call1((v,))?,
+                _ => typ.call0()?,
+            }
+        } else {
+            typ
+        };
+        self.step(py, Resume::Throw(PyErr::from_value(exc)))
+    }
+
+    fn close(&mut self, py: Python<'_>) {
+        if let Some(mut task) = self.task.take() {
+            task.close(py);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! Dependency values of a provider call.
+//!
+//! Most providers take a handful of dependencies. Up to three values are kept
+//! inline and passed to the provider as a Rust tuple, which PyO3 calls through
+//! `PyObject_Vectorcall` with a stack-allocated argument array when the build
+//! targets a stable ABI of 3.12+ (`abi3-py312`) or no stable ABI. The default
+//! `abi3` build targets the 3.9 stable ABI, which has no vectorcall: there PyO3
+//! still builds an argument tuple, whatever Python version runs it. Larger
+//! calls collect into a vector and a `PyTuple`.
+
+use pyo3::prelude::*;
+use pyo3::types::PyTuple;
+
+const INLINE: usize = 3;
+
+pub(crate) enum Args {
+    // Filled from the front
+    Inline([Option<Py<PyAny>>; INLINE]),
+    Heap(Vec<Py<PyAny>>),
+}
+
+impl Args {
+    pub(crate) fn with_capacity(n: usize) -> Self {
+        if n <= INLINE {
+            Args::Inline([None, None, None])
+        } else {
+            Args::Heap(Vec::with_capacity(n))
+        }
+    }
+
+    pub(crate) fn push(&mut self, value: Py<PyAny>) {
+        match self {
+            Args::Inline(slots) => match slots.iter_mut().find(|s| s.is_none()) {
+                Some(slot) => *slot = Some(value),
+                None => {
+                    let mut values: Vec<_> = slots.iter_mut().flat_map(Option::take).collect();
+                    values.push(value);
+                    *self = Args::Heap(values);
+                }
+            },
+            Args::Heap(values) => values.push(value),
+        }
+    }
+
+    /// Call `callable` with the values as positional arguments.
+    pub(crate) fn call<'py>(self, callable: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+        match self {
+            Args::Inline([None, ..]) => callable.call0(),
+            Args::Inline([Some(a), None, _]) => callable.call1((a,)),
+            Args::Inline([Some(a), Some(b), None]) => callable.call1((a, b)),
+            Args::Inline([Some(a), Some(b), Some(c)]) => callable.call1((a, b, c)),
+            Args::Heap(values) => callable.call1(PyTuple::new(callable.py(), values)?),
+        }
+    }
+}
+
+impl FromIterator<Py<PyAny>> for Args {
+    fn from_iter<I: IntoIterator<Item = Py<PyAny>>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut args = Args::with_capacity(iter.size_hint().0);
+        for value in iter {
+            args.push(value);
+        }
+        args
+    }
+}
@@ -0,0 +1,256 @@
+//! Exceptions raised by the Rust core.
+//!
+//! Every FastDI error derives from [`FastDIError`] and from the builtin it
+//! was raised as before (`KeyError`, `RuntimeError`), so existing handlers
+//! keep working. Resolution errors carry the `key` they are about and the
+//! dependency `path` from the resolved root down to it.
+
+use pyo3::prelude::*;
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyKeyError, PyRuntimeError, PyUserWarning};
+use pyo3::intern;
+use pyo3::sync::PyOnceLock;
+use pyo3::types::{PyDict, PyList, PyType};
+
+create_exception!(_fastdi_core, FastDIError, PyException, "Base class of FastDI errors.");
+create_exception!(
+    _fastdi_core,
+    CaptiveDependencyWarning,
+    PyUserWarning,
+    "A singleton depends on a shorter-lived provider."
+);
+
+/// Declares an exception deriving from both [`FastDIError`] and a builtin
+/// exception. `create_exception!` supports a single base only, so the class
+/// is created on first use by calling `type(name, bases, namespace)`.
+macro_rules! fastdi_exception {
+    ($name:ident, $builtin:ty, $doc:expr) => {
+        #[doc = $doc]
+        pub(crate) struct $name;
+
+        impl $name {
+            pub(crate) fn type_object(py: Python<'_>) -> PyResult<&Bound<'_, PyType>> {
+                static TYPE: PyOnceLock<Py<PyType>> = PyOnceLock::new();
+                TYPE.get_or_try_init(py, || {
+                    derive(py, stringify!($name), &py.get_type::<$builtin>(), $doc)
+                })
+                .map(|ty| ty.bind(py))
+            }
+        }
+    };
+}
+
+fastdi_exception!(
+    ProviderNotFound,
+    PyKeyError,
+    "No provider is registered for a key; `path` leads from the resolved root to it."
+);
+fastdi_exception!(
+    DependencyCycle,
+    PyRuntimeError,
+    "Providers depend on each other in a cycle; `path` ends with the key closing it."
+);
+fastdi_exception!(
+    AsyncProviderInSyncContext,
+    PyRuntimeError,
+    "An async provider was reached by synchronous resolution."
+);
+fastdi_exception!(
+    ScopeError,
+    PyRuntimeError,
+    "A request scope is missing, closed, or belongs to another container."
+);
+fastdi_exception!(
+    CaptiveDependency,
+    PyRuntimeError,
+    "A singleton depends on a shorter-lived provider; `path` is the singleton and its dependency."
+);
+fastdi_exception!(
+    ResolutionError,
+    PyRuntimeError,
+    "Provider `key` failed while resolving `path`; the original exception is the `__cause__`."
+);
+fastdi_exception!(
+    DisposeError,
+    PyRuntimeError,
+    "Teardown of one or more values failed; `errors` lists the underlying exceptions."
+);
+
+fn derive(
+    py: Python<'_>,
+    name: &str,
+    builtin: &Bound<'_, PyType>,
+    doc: &str,
+) -> PyResult<Py<PyType>> {
+    let namespace = PyDict::new(py);
+    namespace.set_item(intern!(py, "__module__"), "_fastdi_core")?;
+    namespace.set_item(intern!(py, "__doc__"), doc)?;
+    let bases = (py.get_type::<FastDIError>(), builtin);
+    let ty = py.get_type::<PyType>().call1((name, bases, namespace))?;
+    Ok(ty.cast_into::<PyType>()?.unbind())
+}
+
+/// Add the exception classes to the module.
+pub(crate) fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    m.add("FastDIError", py.get_type::<FastDIError>())?;
+    m.add("ProviderNotFound", ProviderNotFound::type_object(py)?)?;
+    m.add("DependencyCycle", DependencyCycle::type_object(py)?)?;
+    m.add("AsyncProviderInSyncContext", AsyncProviderInSyncContext::type_object(py)?)?;
+    m.add("ScopeError", ScopeError::type_object(py)?)?;
+    m.add("CaptiveDependency", CaptiveDependency::type_object(py)?)?;
+    m.add("CaptiveDependencyWarning", py.get_type::<CaptiveDependencyWarning>())?;
+    m.add("ResolutionError", ResolutionError::type_object(py)?)?;
+    m.add("DisposeError", DisposeError::type_object(py)?)?;
+    Ok(())
+}
+
+/// Instantiate `ty` with `message`, setting `key` and `path` (the keys from
+/// the resolved root down to `key`).
+fn raise(
+    py: Python<'_>,
+    ty: PyResult<&Bound<'_, PyType>>,
+    message: String,
+    key: Option<&str>,
+    path: &[String],
+) -> PyErr {
+    let build = || -> PyResult<PyErr> {
+        let err = ty?.call1((message,))?;
+        err.setattr(intern!(py, "key"), key)?;
+        err.setattr(intern!(py, "path"), PyList::new(py, path)?)?;
+        Ok(PyErr::from_value(err))
+    };
+    build().unwrap_or_else(|err| err)
+}
+
+/// `path` followed by `key`.
+fn through(path: &[String], key: &str) -> Vec<String> {
+    path.iter().cloned().chain(std::iter::once(key.to_string())).collect()
+}
+
+/// Message for a missing provider for `key`, required through the dependency
+/// `path` from a root (empty when `key` is itself a root).
+pub(crate) fn missing_message(key: &str, path: &[String]) -> String {
+    if path.is_empty() {
+        return format!("No provider registered for key: {}", key);
+    }
+    format!("No provider registered for key: {} ({} -> {} (missing))", key, path.join(" -> "), key)
+}
+
+/// Message for a cycle closed by `key`, which is already on the dependency
+/// `path`: the cycle members and the path that reached the cycle.
+pub(crate) fn cycle_message(key: &str, path: &[String]) -> String {
+    let start = path.iter().position(|k| k == key).unwrap_or(0);
+    let cycle = format!("{} -> {}", path[start..].join(" -> "), key);
+    if start == 0 {
+        return format!("Dependency cycle detected at key: {} ({})", key, cycle);
+    }
+    format!(
+        "Dependency cycle detected at key: {} ({}, reached via {})",
+        key,
+        cycle,
+        path[..start].join(" -> ")
+    )
+}
+
+/// Message for singleton `key` depending on `dep`, which has the shorter
+/// lifetime `scope`.
+pub(crate) fn captive_message(key: &str, dep: &str, scope: &str) -> String {
+    format!(
+        "Captive dependency: '{}' (singleton) depends on '{}' ({}), whose value would be kept \
+         for the singleton's lifetime",
+        key, dep, scope
+    )
+}
+
+/// Singleton `key` depending on the shorter-lived `dep`; see [`captive_message`].
+pub(crate) fn captive_dependency(key: &str, dep: &str, scope: &str) -> PyErr {
+    let message = captive_message(key, dep, scope);
+    let path = [key.to_string(), dep.to_string()];
+    Python::attach(|py| raise(py, CaptiveDependency::type_object(py), message, Some(key), &path))
+}
+
+/// Missing provider for `key`; see [`missing_message`].
+pub(crate) fn provider_not_found(key: &str, path: &[String]) -> PyErr {
+    let message = missing_message(key, path);
+    Python::attach(|py| {
+        raise(py, ProviderNotFound::type_object(py), message, Some(key), &through(path, key))
+    })
+}
+
+/// Cycle closed by `key`; see [`cycle_message`].
+pub(crate) fn dependency_cycle(key: &str, path: &[String]) -> PyErr {
+    let message = cycle_message(key, path);
+    Python::attach(|py| {
+        raise(py, DependencyCycle::type_object(py), message, Some(key), &through(path, key))
+    })
+}
+
+/// Singleton `key` waited for by a caller its initialization waits for.
+pub(crate) fn flight_cycle(key: &str, message: &str) -> PyErr {
+    let path = [key.to_string()];
+    Python::attach(|py| {
+        raise(py, DependencyCycle::type_object(py), message.to_string(), Some(key), &path)
+    })
+}
+
+/// Async provider `key` reached by sync resolution through `path`.
+pub(crate) fn async_in_sync(key: &str, path: &[String]) -> PyErr {
+    let message = format!("Provider for key '{}' is async and requires async resolution", key);
+    Python::attach(|py| {
+        let ty = AsyncProviderInSyncContext::type_object(py);
+        raise(py, ty, message, Some(key), &through(path, key))
+    })
+}
+
+/// Value of async generator/context-manager provider `key` entered synchronously.
+pub(crate) fn async_enter(key: &str) -> PyErr {
+    let message = format!("Provider for key '{}' must be entered asynchronously", key);
+    Python::attach(|py| {
+        let ty = AsyncProviderInSyncContext::type_object(py);
+        raise(py, ty, message, Some(key), &through(&[], key))
+    })
+}
+
+/// Misuse of request scopes, about provider `key` if given.
+pub(crate) fn scope_error(key: Option<&str>, message: impl Into<String>) -> PyErr {
+    let path: Vec<String> = key.map(|k| vec![k.to_string()]).unwrap_or_default();
+    Python::attach(|py| raise(py, ScopeError::type_object(py), message.into(), key, &path))
+}
+
+/// Wrap the failure of provider `key`, reached through `path` from the
+/// resolved root, into a `ResolutionError` caused by it. FastDI's own errors
+/// and exceptions that are not `Exception`s (e.g. cancellation) pass through.
+pub(crate) fn resolution_error(py: Python<'_>, err: PyErr, key: &str, path: &[String]) -> PyErr {
+    if !err.is_instance_of::<PyException>(py) || err.is_instance_of::<FastDIError>(py) {
+        return err;
+    }
+    let cause = err.value(py).repr().map(|r| r.to_string()).unwrap_or_default();
+    let path = through(path, key);
+    let message =
+        format!("Provider for key '{}' failed ({}): {}", key, path.join(" -> "), cause);
+    let wrapped = raise(py, ResolutionError::type_object(py), message, Some(key), &path);
+    wrapped.set_cause(py, Some(err));
+    wrapped
+}
+
+/// Combine the errors collected while tearing down values.
+///
+/// Returns a `DisposeError` carrying every error (the first as its cause).
+/// Exceptions that are not `Exception`s (e.g. `asyncio.CancelledError`) are
+/// propagated as they are.
+pub(crate) fn aggregate(py: Python<'_>, errors: Vec<PyErr>) -> PyResult<()> {
+    if errors.is_empty() {
+        return Ok(());
+    }
+    if let Some(i) = errors.iter().position(|e| !e.is_instance_of::<PyException>(py)) {
+        return Err(errors.into_iter().nth(i).unwrap());
+    }
+    let err = DisposeError::type_object(py)?
+        .call1((format!("{} error(s) during teardown", errors.len()),))?;
+    let values: Vec<_> = errors.iter().map(|e| e.value(py).clone()).collect();
+    err.setattr(intern!(py, "errors"), PyList::new(py, values)?)?;
+    let err = PyErr::from_value(err);
+    err.set_cause(py, errors.into_iter().next());
+    Err(err)
+}
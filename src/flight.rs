@@ -0,0 +1,312 @@
+//! Single-flight singleton initialization.
+//!
+//! The first caller that misses a singleton's cache claims a [`Flight`] and
+//! builds the value; concurrent callers wait for it instead of building a
+//! second one. Threads block on a condition variable (with the GIL released),
+//! asyncio tasks await a future that the builder wakes up through
+//! `loop.call_soon_threadsafe`. Waiters re-check the cache once the flight
+//! lands, so a failed initialization is retried by the next caller.
+//!
+//! Callers record what they wait for in a process-wide wait-for graph: the
+//! flight a thread blocks on or a task awaits, and the tasks a gathered level
+//! awaits. Before waiting, a caller follows the graph from the flight's owner;
+//! if it leads back to the caller, the wait could never end and a
+//! `DependencyCycle` is raised instead.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, ThreadId};
+use pyo3::prelude::*;
+use pyo3::intern;
+use pyo3::sync::PyOnceLock;
+use pyo3::types::PyCFunction;
+
+use crate::errors;
+use crate::Provider;
+
+/// A caller initializing or waiting for singletons: a thread, or an asyncio
+/// task running on one.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Actor {
+    Thread(ThreadId),
+    // Task object address, and the thread running its event loop
+    Task(usize, ThreadId),
+}
+
+static CURRENT_TASK: PyOnceLock<Py<PyAny>> = PyOnceLock::new();
+
+impl Actor {
+    /// The running asyncio task if `in_task` and there is one, otherwise the
+    /// current thread.
+    pub(crate) fn current(py: Python<'_>, in_task: bool) -> Self {
+        if in_task {
+            let task = CURRENT_TASK
+                .import(py, "asyncio", "current_task")
+                .and_then(|current_task| current_task.call0());
+            match task {
+                Ok(task) if !task.is_none() => return Self::task(&task),
+                // No running loop or task
+                _ => {}
+            }
+        }
+        Actor::Thread(thread::current().id())
+    }
+
+    /// `task`, running on the current thread.
+    pub(crate) fn task(task: &Bound<'_, PyAny>) -> Self {
+        Actor::Task(task.as_ptr() as usize, thread::current().id())
+    }
+
+    fn thread(self) -> ThreadId {
+        match self {
+            Actor::Thread(thread) | Actor::Task(_, thread) => thread,
+        }
+    }
+
+    /// Whether `self` can never see work done by `other` finish while it
+    /// waits for it.
+    fn stuck_behind(self, other: Actor) -> bool {
+        match (self, other) {
+            _ if self == other => true,
+            // A blocked thread also stops the tasks of its event loop
+            (Actor::Thread(thread), other) => other.thread() == thread,
+            // A thread's own initialization is below the running task on its stack
+            (Actor::Task(_, thread), Actor::Thread(other)) => other == thread,
+            (Actor::Task(..), Actor::Task(..)) => false,
+        }
+    }
+}
+
+/// What an actor waits for.
+enum Blocker {
+    Flight(Arc<Flight>),
+    Task(Actor),
+}
+
+impl Blocker {
+    fn actor(&self) -> Actor {
+        match self {
+            Blocker::Flight(flight) => *flight.owner.lock().unwrap(),
+            Blocker::Task(task) => *task,
+        }
+    }
+}
+
+/// The wait-for graph: what each waiting actor waits for, by registration id.
+type WaitGraph = HashMap<Actor, Vec<(u64, Blocker)>>;
+
+static WAITING: Mutex<Option<WaitGraph>> = Mutex::new(None);
+static NEXT_WAIT: AtomicU64 = AtomicU64::new(0);
+
+/// Registration of an actor waiting for a flight or a task; removed on drop.
+pub(crate) struct Waiting {
+    actor: Actor,
+    id: u64,
+}
+
+impl Waiting {
+    /// Record that `actor` waits for `blocker`. With `key`, the wait is
+    /// rejected if following the graph from `blocker` leads back to `actor`.
+    fn register(actor: Actor, blocker: Blocker, key: Option<&str>) -> PyResult<Self> {
+        let mut graph = WAITING.lock().unwrap();
+        let waiting = graph.get_or_insert_with(HashMap::new);
+        if let Some(key) = key {
+            let owner = blocker.actor();
+            if leads_back(waiting, actor, owner) {
+                return Err(errors::flight_cycle(key, &cycle_message(key, actor, owner)));
+            }
+        }
+        let id = NEXT_WAIT.fetch_add(1, Ordering::Relaxed);
+        waiting.entry(actor).or_default().push((id, blocker));
+        Ok(Self { actor, id })
+    }
+
+    /// Record that `parent` awaits the gathered `task`.
+    pub(crate) fn on_task(parent: Actor, task: Actor) -> Self {
+        Self::register(parent, Blocker::Task(task), None).expect("unchecked waits succeed")
+    }
+}
+
+impl Drop for Waiting {
+    fn drop(&mut self) {
+        let mut graph = WAITING.lock().unwrap();
+        let Some(waiting) = graph.as_mut() else { return };
+        if let Some(blockers) = waiting.get_mut(&self.actor) {
+            blockers.retain(|(id, _)| *id != self.id);
+            if blockers.is_empty() {
+                waiting.remove(&self.actor);
+            }
+        }
+    }
+}
+
+/// Whether `actor` waiting for work of `owner` closes a cycle in the graph.
+fn leads_back(waiting: &WaitGraph, actor: Actor, owner: Actor) -> bool {
+    let mut stack = vec![owner];
+    let mut seen = HashSet::new();
+    while let Some(next) = stack.pop() {
+        if actor.stuck_behind(next) {
+            return true;
+        }
+        if !seen.insert(next) {
+            continue;
+        }
+        let mut blockers: Vec<&(u64, Blocker)> = waiting.get(&next).into_iter().flatten().collect();
+        if let Actor::Task(_, thread) = next {
+            // A task is also stopped while its thread blocks
+            blockers.extend(waiting.get(&Actor::Thread(thread)).into_iter().flatten());
+        }
+        stack.extend(blockers.into_iter().map(|(_, blocker)| blocker.actor()));
+    }
+    false
+}
+
+/// Message for `actor` waiting for singleton `key`, initialized by `owner`.
+fn cycle_message(key: &str, actor: Actor, owner: Actor) -> String {
+    match (actor, owner) {
+        (Actor::Thread(_), Actor::Thread(_)) if actor == owner => {
+            format!("Singleton '{}' is already being initialized by this thread", key)
+        }
+        (Actor::Task(..), Actor::Task(..)) if actor == owner => {
+            format!("Singleton '{}' is already being initialized by this task", key)
+        }
+        (Actor::Thread(thread), Actor::Task(_, owner)) if thread == owner => format!(
+            "Singleton '{}' is being initialized by a task on this thread; resolve it \
+             asynchronously",
+            key
+        ),
+        _ => format!(
+            "Singleton '{}' is being initialized by a caller waiting, directly or through \
+             others, for this one",
+            key
+        ),
+    }
+}
+
+/// An in-progress singleton initialization.
+pub(crate) struct Flight {
+    // Moves to the gathered task building the value
+    owner: Mutex<Actor>,
+    state: Mutex<FlightState>,
+    landed: Condvar,
+}
+
+#[derive(Default)]
+struct FlightState {
+    done: bool,
+    // (event loop, future) of waiting tasks
+    futures: Vec<(Py<PyAny>, Py<PyAny>)>,
+}
+
+impl Flight {
+    pub(crate) fn new(owner: Actor) -> Self {
+        Self {
+            owner: Mutex::new(owner),
+            state: Mutex::new(FlightState::default()),
+            landed: Condvar::new(),
+        }
+    }
+
+    /// Block the current thread until the flight lands.
+    ///
+    /// Waiting on a flight whose owner waits for this thread could never
+    /// finish, so that is reported instead.
+    pub(crate) fn wait(self: &Arc<Self>, py: Python<'_>, key: &str) -> PyResult<()> {
+        let actor = Actor::Thread(thread::current().id());
+        let _waiting = Waiting::register(actor, Blocker::Flight(self.clone()), Some(key))?;
+        py.detach(|| {
+            let mut state = self.state.lock().unwrap();
+            while !state.done {
+                state = self.landed.wait(state).unwrap();
+            }
+        });
+        Ok(())
+    }
+
+    /// A future of the running event loop that completes when the flight
+    /// lands, with the running task's registration as its waiter; `None` if
+    /// it already has landed. Like [`Flight::wait`], waits that could never
+    /// finish are reported.
+    pub(crate) fn subscribe<'py>(
+        self: &Arc<Self>,
+        py: Python<'py>,
+        key: &str,
+    ) -> PyResult<Option<(Bound<'py, PyAny>, Waiting)>> {
+        static GET_RUNNING_LOOP: PyOnceLock<Py<PyAny>> = PyOnceLock::new();
+        let actor = Actor::current(py, true);
+        let waiting = Waiting::register(actor, Blocker::Flight(self.clone()), Some(key))?;
+        let event_loop = GET_RUNNING_LOOP.import(py, "asyncio", "get_running_loop")?.call0()?;
+        let future = event_loop.call_method0(intern!(py, "create_future"))?;
+        let mut state = self.state.lock().unwrap();
+        if state.done {
+            return Ok(None);
+        }
+        state.futures.push((event_loop.unbind(), future.clone().unbind()));
+        Ok(Some((future, waiting)))
+    }
+
+    fn land(&self, py: Python<'_>) {
+        let futures = {
+            let mut state = self.state.lock().unwrap();
+            state.done = true;
+            std::mem::take(&mut state.futures)
+        };
+        self.landed.notify_all();
+        if futures.is_empty() {
+            return;
+        }
+        let Ok(wake) = WAKE.get_or_try_init(py, || -> PyResult<_> {
+            Ok(PyCFunction::new_closure(py, None, None, |args, _kwargs| -> PyResult<()> {
+                let future = args.get_item(0)?;
+                if !future.call_method0(intern!(args.py(), "done"))?.is_truthy()? {
+                    future.call_method1(intern!(args.py(), "set_result"), (args.py().None(),))?;
+                }
+                Ok(())
+            })?
+            .into_any()
+            .unbind())
+        }) else {
+            return;
+        };
+        for (event_loop, future) in futures {
+            // The loop may already be closed; its tasks are gone then
+            let _ = event_loop
+                .bind(py)
+                .call_method1(intern!(py, "call_soon_threadsafe"), (wake, future));
+        }
+    }
+}
+
+// Completes a waiter's future unless it was cancelled
+static WAKE: PyOnceLock<Py<PyAny>> = PyOnceLock::new();
+
+/// Held by the caller building a singleton; landing the flight on drop wakes
+/// the waiters, whether the value was stored or the build failed.
+pub(crate) struct FlightGuard {
+    provider: Arc<Provider>,
+    flight: Arc<Flight>,
+}
+
+impl FlightGuard {
+    pub(crate) fn new(provider: Arc<Provider>, flight: Arc<Flight>) -> Self {
+        Self { provider, flight }
+    }
+
+    /// Hand the initialization over to the gathered task computing it.
+    pub(crate) fn hand_over(&self, task: Actor) {
+        *self.flight.owner.lock().unwrap() = task;
+    }
+
+    /// Land the flight without waiting for the guard to be dropped.
+    pub(crate) fn release(&self, py: Python<'_>) {
+        self.provider.clear_flight(&self.flight);
+        self.flight.land(py);
+    }
+}
+
+impl Drop for FlightGuard {
+    fn drop(&mut self) {
+        Python::attach(|py| self.release(py));
+    }
+}
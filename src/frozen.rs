@@ -0,0 +1,145 @@
+//! Frozen containers (`Container.freeze()`).
+//!
+//! Once the graph is final, `freeze()` turns the base registrations into an
+//! immutable table indexed by key id (see `intern`), and singleton values are
+//! published once built. Resolution then walks the table without taking the
+//! container lock; registration and overrides are rejected.
+
+use std::sync::{Arc, OnceLock};
+use pyo3::prelude::*;
+use pyo3::exceptions::PyRuntimeError;
+
+use crate::args::Args;
+use crate::intern::{Interner, KeyId};
+use crate::scope::Scope;
+use crate::{errors, Claim, Container, ContainerInner, Provider};
+
+struct FrozenNode {
+    key: Arc<str>,
+    provider: Arc<Provider>,
+    // The singleton value once built, unless it can expire; read without locking
+    published: OnceLock<Py<PyAny>>,
+}
+
+/// Immutable provider table of a frozen container, indexed by key id.
+pub(crate) struct Frozen {
+    interner: Interner,
+    nodes: Vec<Option<FrozenNode>>,
+}
+
+impl ContainerInner {
+    /// Build the frozen table; every dependency must have a provider and no
+    /// override layer may be active.
+    pub(crate) fn freeze(&self, py: Python<'_>) -> PyResult<Frozen> {
+        if !self.overrides.is_empty() {
+            return Err(PyRuntimeError::new_err("Cannot freeze a container with active overrides"));
+        }
+        let mut nodes: Vec<Option<FrozenNode>> = Vec::new();
+        nodes.resize_with(self.interner.len(), || None);
+        for (id, provider) in self.providers.iter() {
+            let key = self.interner.name(id);
+            if let Some(&d) = provider.meta.deps.iter().find(|&&d| !self.providers.contains(d)) {
+                let path = [key.to_string()];
+                return Err(errors::provider_not_found(self.interner.name(d), &path));
+            }
+            let provider = provider.clone();
+            let node = FrozenNode { key: key.clone(), provider, published: OnceLock::new() };
+            // Singletons built before freezing (e.g. by `warmup()`) are published right away
+            node.publish_cached(py);
+            nodes[id.index()] = Some(node);
+        }
+        Ok(Frozen { interner: self.interner.clone(), nodes })
+    }
+}
+
+impl FrozenNode {
+    /// Publish the cached singleton of this node, unless it can expire.
+    fn publish_cached(&self, py: Python<'_>) {
+        if let Some(value) = self.provider.permanent(py) {
+            let _ = self.published.set(value);
+        }
+    }
+}
+
+impl Frozen {
+    fn node(&self, id: KeyId) -> &FrozenNode {
+        self.nodes[id.index()].as_ref().expect("frozen dependencies are checked by freeze()")
+    }
+}
+
+impl Container {
+    /// [`Container::resolve_key`] over the frozen table.
+    pub(crate) fn resolve_frozen(
+        &self,
+        py: Python<'_>,
+        frozen: &Frozen,
+        key: &str,
+        scope: Option<&Scope>,
+    ) -> PyResult<Py<PyAny>> {
+        let id = frozen.interner.id(key).filter(|id| frozen.nodes[id.index()].is_some());
+        let id = id.ok_or_else(|| errors::provider_not_found(key, &[]))?;
+        self.resolve_node(py, frozen, id, scope, &mut Vec::new())
+    }
+
+    fn resolve_node(
+        &self,
+        py: Python<'_>,
+        frozen: &Frozen,
+        id: KeyId,
+        scope: Option<&Scope>,
+        path: &mut Vec<KeyId>,
+    ) -> PyResult<Py<PyAny>> {
+        let node = frozen.node(id);
+        if let Some(value) = node.published.get() {
+            return Ok(value.clone_ref(py));
+        }
+        if path.contains(&id) {
+            return Err(errors::dependency_cycle(&node.key, &frozen.interner.names(path)));
+        }
+        let provider = &node.provider;
+        if let Some(cached) = provider.lookup(py, scope) {
+            // Built through another path (plans, `@inject`, async resolution)
+            if provider.is_singleton() {
+                node.publish_cached(py);
+            }
+            return Ok(cached);
+        }
+        if provider.meta.is_async {
+            return Err(errors::async_in_sync(&node.key, &frozen.interner.names(path)));
+        }
+        let captive = self.captive_deps(provider, |d| {
+            let dep = frozen.node(d);
+            Some((&*dep.key, &dep.provider))
+        });
+        for (dep, scope) in captive {
+            self.captive_edge(&node.key, &dep, scope)?;
+        }
+
+        let mut args = Args::with_capacity(provider.meta.deps.len());
+        path.push(id);
+        for &d in provider.meta.deps.iter() {
+            args.push(self.resolve_node(py, frozen, d, scope, path)?);
+        }
+        path.pop();
+
+        let _flight = match provider.claim_sync(py, &node.key, scope)? {
+            Claim::Cached(cached) => return Ok(cached),
+            Claim::Build(guard) => guard,
+            Claim::Wait(_) => unreachable!("claim_sync waits for other initializations"),
+        };
+        let deps = provider.meta.deps.iter().map(|&d| &frozen.node(d).provider);
+        let inherited = provider.inherited_from(deps);
+        let value = provider.call(py, &node.key, scope, args, inherited).map_err(|err| {
+            if self.wrap_errors {
+                errors::resolution_error(py, err, &node.key, &frozen.interner.names(path))
+            } else {
+                err
+            }
+        })?;
+        // Values that can expire keep going through the cache slot
+        if provider.is_singleton() && provider.meta.ttl.is_none() && inherited.is_none() {
+            let _ = node.published.set(value.clone_ref(py));
+        }
+        Ok(value)
+    }
+}
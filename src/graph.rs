@@ -0,0 +1,273 @@
+//! Dependency graph queries and export (`Container.export_graph()`).
+//!
+//! The graph is the active wiring: one node per registered key (base
+//! registrations and override layers), with edges from each dependency to its
+//! dependent, as in the diagrams of `docs/architecture.md`.
+
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+use pyo3::sync::PyOnceLock;
+use pyo3::types::{PyDict, PyList};
+
+use crate::intern::KeyId;
+use crate::{errors, ContainerInner};
+
+pub(crate) struct GraphNode {
+    key: String,
+    // `None` for dependencies without a provider
+    scope: Option<&'static str>,
+    is_async: bool,
+    // An override layer provides the key in place of the base registration
+    // or a lower layer
+    overridden: bool,
+    // A singleton value is cached and has not expired
+    cached: bool,
+    deps: Vec<String>,
+}
+
+impl GraphNode {
+    fn missing(&self) -> bool {
+        self.scope.is_none()
+    }
+}
+
+impl ContainerInner {
+    /// Every registered key, sorted.
+    pub(crate) fn keys(&self) -> Vec<String> {
+        let keys: BTreeSet<&str> = self.entries().map(|(k, _)| k).collect();
+        keys.into_iter().map(String::from).collect()
+    }
+
+    /// Dependencies of `key`: direct ones in declaration order, or every key
+    /// reachable from it (sorted) when `transitive`. Dependencies without a
+    /// provider are included but not followed.
+    pub(crate) fn dependencies(&self, key: &str, transitive: bool) -> PyResult<Vec<String>> {
+        let provider = self.get_key(key).ok_or_else(|| errors::provider_not_found(key, &[]))?;
+        if !transitive {
+            let mut seen = HashSet::new();
+            let deps = self.dep_names(provider).into_iter().filter(|d| seen.insert(*d));
+            return Ok(deps.map(String::from).collect());
+        }
+        let mut found = BTreeSet::new();
+        let mut stack = self.dep_names(provider);
+        while let Some(dep) = stack.pop() {
+            if dep != key && found.insert(dep.to_string()) {
+                if let Some(p) = self.get_key(dep) {
+                    stack.extend(self.dep_names(p));
+                }
+            }
+        }
+        Ok(found.into_iter().collect())
+    }
+
+    /// Keys whose active provider depends on `key`, sorted; with `transitive`,
+    /// every key that would be affected by a change to `key`.
+    pub(crate) fn dependents(&self, key: &str, transitive: bool) -> Vec<String> {
+        let Some(target) = self.interner.id(key) else { return Vec::new() };
+        // Reverse edges of the active providers, by key id
+        let mut reverse: Vec<Vec<KeyId>> = vec![Vec::new(); self.interner.len()];
+        let mut listed = vec![false; self.interner.len()];
+        let layers = std::iter::once(&self.providers).chain(&self.overrides);
+        for (id, _) in layers.flat_map(|l| l.iter()) {
+            if std::mem::replace(&mut listed[id.index()], true) {
+                continue;
+            }
+            for &dep in self.get(id).into_iter().flat_map(|p| p.meta.deps.iter()) {
+                reverse[dep.index()].push(id);
+            }
+        }
+        let mut seen = vec![false; self.interner.len()];
+        seen[target.index()] = true;
+        let mut found = BTreeSet::new();
+        let mut queue = VecDeque::from([target]);
+        while let Some(id) = queue.pop_front() {
+            for &dependent in &reverse[id.index()] {
+                if std::mem::replace(&mut seen[dependent.index()], true) {
+                    continue;
+                }
+                found.insert(self.interner.name(dependent).to_string());
+                if transitive {
+                    queue.push_back(dependent);
+                }
+            }
+        }
+        found.into_iter().collect()
+    }
+
+    /// Which entry provides `key`: 0 for the base registration, `n` for the
+    /// `n`-th active override layer.
+    pub(crate) fn provider_layer(&self, key: &str) -> PyResult<usize> {
+        let not_found = || errors::provider_not_found(key, &[]);
+        let id = self.interner.id(key).ok_or_else(not_found)?;
+        if let Some(i) = self.overrides.iter().rposition(|l| l.contains(id)) {
+            return Ok(i + 1);
+        }
+        if self.providers.contains(id) {
+            return Ok(0);
+        }
+        Err(not_found())
+    }
+
+    /// Nodes of the active graph, sorted by key; missing dependencies are
+    /// included as nodes without a scope. Cached values expired at `now` are
+    /// not reported as cached.
+    pub(crate) fn graph_nodes(&self, now: f64) -> Vec<GraphNode> {
+        let registered: BTreeSet<&str> = self.entries().map(|(k, _)| k).collect();
+        let mut nodes: Vec<GraphNode> = registered
+            .iter()
+            .filter_map(|&key| {
+                let provider = self.get_key(key)?;
+                let id = provider.id;
+                let shadowed = |layer: usize| {
+                    self.providers.contains(id)
+                        || self.overrides[..layer].iter().any(|l| l.contains(id))
+                };
+                let slot = provider.cache.read().unwrap();
+                Some(GraphNode {
+                    key: key.to_string(),
+                    scope: Some(provider.meta.lifetime.name()),
+                    is_async: provider.meta.is_async,
+                    overridden: self
+                        .overrides
+                        .iter()
+                        .rposition(|l| l.contains(id))
+                        .is_some_and(shadowed),
+                    cached: slot.value.as_ref().is_some_and(|c| !c.expiry.expired(now)),
+                    deps: self.dep_names(provider).into_iter().map(String::from).collect(),
+                })
+            })
+            .collect();
+        let missing: BTreeSet<String> = nodes
+            .iter()
+            .flat_map(|n| n.deps.iter())
+            .filter(|d| !registered.contains(d.as_str()))
+            .cloned()
+            .collect();
+        nodes.extend(missing.into_iter().map(|key| GraphNode {
+            key,
+            scope: None,
+            is_async: false,
+            overridden: false,
+            cached: false,
+            deps: Vec::new(),
+        }));
+        nodes.sort_by(|a, b| a.key.cmp(&b.key));
+        nodes
+    }
+}
+
+/// Render `nodes` as `"dot"`, `"mermaid"` or `"json"`.
+pub(crate) fn render(py: Python<'_>, nodes: &[GraphNode], format: &str) -> PyResult<String> {
+    match format {
+        "dot" => Ok(to_dot(nodes)),
+        "mermaid" => Ok(to_mermaid(nodes)),
+        "json" => to_json(py, nodes),
+        other => Err(PyValueError::new_err(format!(
+            "Unknown graph format '{}'; expected 'dot', 'mermaid' or 'json'",
+            other
+        ))),
+    }
+}
+
+/// Second label line: scope and flags, e.g. `singleton, async, cached`.
+fn describe(node: &GraphNode) -> String {
+    let Some(scope) = node.scope else {
+        return "missing".to_string();
+    };
+    let mut parts = vec![scope];
+    if node.is_async {
+        parts.push("async");
+    }
+    if node.overridden {
+        parts.push("overridden");
+    }
+    if node.cached {
+        parts.push("cached");
+    }
+    parts.join(", ")
+}
+
+fn to_dot(nodes: &[GraphNode]) -> String {
+    let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+    let quote = |s: &str| format!("\"{}\"", escape(s));
+    let mut out = String::from("digraph fastdi {\n    node [shape=box];\n");
+    for node in nodes {
+        let label = format!("\"{}\\n{}\"", escape(&node.key), describe(node));
+        let style = if node.missing() {
+            " style=dashed color=red"
+        } else if node.overridden {
+            " style=filled fillcolor=lightyellow"
+        } else {
+            ""
+        };
+        out.push_str(&format!("    {} [label={}{}];\n", quote(&node.key), label, style));
+    }
+    for node in nodes {
+        for dep in &node.deps {
+            out.push_str(&format!("    {} -> {};\n", quote(dep), quote(&node.key)));
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn to_mermaid(nodes: &[GraphNode]) -> String {
+    // Keys are not valid Mermaid ids; nodes are numbered in key order
+    let id = |key: &str| nodes.binary_search_by(|n| n.key.as_str().cmp(key)).unwrap_or(0);
+    let escape = |s: &str| s.replace('"', "#quot;").replace('<', "#lt;").replace('>', "#gt;");
+    let mut out = String::from("flowchart TD\n");
+    for (i, node) in nodes.iter().enumerate() {
+        let label = format!("{}<br/>{}", escape(&node.key), describe(node));
+        out.push_str(&format!("    n{}[\"{}\"]\n", i, label));
+    }
+    for node in nodes {
+        for dep in &node.deps {
+            out.push_str(&format!("    n{} --> n{}\n", id(dep), id(&node.key)));
+        }
+    }
+    // Same highlighting as the DOT output
+    let missing: Vec<usize> = (0..nodes.len()).filter(|&i| nodes[i].missing()).collect();
+    let overridden: Vec<usize> = (0..nodes.len()).filter(|&i| nodes[i].overridden).collect();
+    add_class(&mut out, "missing", "stroke:#d00,stroke-dasharray:4", &missing);
+    add_class(&mut out, "overridden", "fill:#ffffe0", &overridden);
+    out
+}
+
+fn add_class(out: &mut String, name: &str, style: &str, members: &[usize]) {
+    if members.is_empty() {
+        return;
+    }
+    let ids: Vec<String> = members.iter().map(|i| format!("n{}", i)).collect();
+    out.push_str(&format!("    classDef {} {}\n", name, style));
+    out.push_str(&format!("    class {} {}\n", ids.join(","), name));
+}
+
+static DUMPS: PyOnceLock<Py<PyAny>> = PyOnceLock::new();
+
+fn to_json(py: Python<'_>, nodes: &[GraphNode]) -> PyResult<String> {
+    let json_nodes = PyList::empty(py);
+    let edges = PyList::empty(py);
+    for node in nodes {
+        let item = PyDict::new(py);
+        item.set_item("key", &node.key)?;
+        item.set_item("scope", node.scope)?;
+        item.set_item("async", node.is_async)?;
+        item.set_item("overridden", node.overridden)?;
+        item.set_item("cached", node.cached)?;
+        item.set_item("missing", node.missing())?;
+        json_nodes.append(item)?;
+        for dep in &node.deps {
+            let edge = PyDict::new(py);
+            edge.set_item("dependent", &node.key)?;
+            edge.set_item("dependency", dep)?;
+            edges.append(edge)?;
+        }
+    }
+    let graph = PyDict::new(py);
+    graph.set_item("nodes", json_nodes)?;
+    graph.set_item("edges", edges)?;
+    let kwargs = PyDict::new(py);
+    kwargs.set_item("indent", 2)?;
+    DUMPS.import(py, "json", "dumps")?.call((graph,), Some(&kwargs))?.extract()
+}
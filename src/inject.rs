@@ -0,0 +1,163 @@
+//! Injected call sites (`@inject`, `@inject_method`).
+//!
+//! An `Injector` is created once per decorated function. It keeps the
+//! dependency parameters of the function with their positions and the plan
+//! compiled for their keys, so a call only checks which parameters the caller
+//! passed, resolves the others and calls the function, all without binding a
+//! Python signature. Calls passing some dependencies run a plan compiled for
+//! the missing ones, cached per set of missing parameters.
+//!
+//! PyO3 classes are called through `tp_call`: PyO3 has no way to give them a
+//! vectorcall slot, so each call still receives an argument tuple.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use pyo3::prelude::*;
+use pyo3::exceptions::PyTypeError;
+use pyo3::sync::PyOnceLock;
+use pyo3::types::{PyDict, PyString, PyTuple};
+
+use crate::plan::Plan;
+use crate::Container;
+
+/// A dependency parameter of the decorated function.
+struct Param {
+    // Index in `Injector::params`
+    index: usize,
+    name: Py<PyString>,
+    key: String,
+    // Index among the positional parameters, unless keyword-only
+    position: Option<usize>,
+    // Whether it can be passed by keyword (not positional-only)
+    keyword: bool,
+}
+
+/// Callable wrapping a function whose `Depends` parameters are resolved from a
+/// container when the caller does not pass them.
+///
+/// Created by `Container.injector(func, params)`; binds like a function when
+/// used as a method.
+#[pyclass(frozen, dict, module = "_fastdi_core")]
+pub(crate) struct Injector {
+    container: Py<Container>,
+    target: Py<PyAny>,
+    params: Vec<Param>,
+    // Compiled for every dependency parameter, in `params` order
+    plan: Plan,
+    // Compiled for the missing parameters of partial calls, by bit mask of
+    // their indices in `params`
+    partial: RwLock<HashMap<u64, Arc<Plan>>>,
+}
+
+static METHOD_TYPE: PyOnceLock<Py<PyAny>> = PyOnceLock::new();
+
+impl Injector {
+    pub(crate) fn new(
+        py: Python<'_>,
+        container: Py<Container>,
+        target: Py<PyAny>,
+        params: Vec<(String, String, Option<usize>, bool)>,
+        plan: Plan,
+    ) -> Self {
+        let params = params
+            .into_iter()
+            .enumerate()
+            .map(|(index, (name, key, position, keyword))| Param {
+                index,
+                name: PyString::intern(py, &name).unbind(),
+                key,
+                position,
+                keyword,
+            })
+            .collect();
+        Self { container, target, params, plan, partial: RwLock::new(HashMap::new()) }
+    }
+
+    /// Values of the `missing` parameters of a partial call.
+    fn resolve_partial(&self, py: Python<'_>, missing: &[&Param]) -> PyResult<Vec<Py<PyAny>>> {
+        let keys = || missing.iter().map(|p| p.key.clone()).collect::<Vec<_>>();
+        if self.params.len() > 64 {
+            return self.container.get().resolve_many_plan(py, keys());
+        }
+        let mask = missing.iter().fold(0u64, |mask, p| mask | 1 << p.index);
+        let cached = self.partial.read().unwrap().get(&mask).cloned();
+        let plan = match cached {
+            Some(plan) => plan,
+            None => {
+                // Not through `compile()`: the injector's plan already records
+                // its keys for `validate()`
+                let keys = keys();
+                let compiled = self.container.get().compile_plan(&keys, false)?;
+                let plan = Arc::new(Plan::new(self.container.clone_ref(py), keys, false, compiled));
+                self.partial.write().unwrap().entry(mask).or_insert(plan).clone()
+            }
+        };
+        plan.execute(py)
+    }
+}
+
+#[pymethods]
+impl Injector {
+    #[pyo3(signature = (*args, **kwargs))]
+    fn __call__(
+        &self,
+        py: Python<'_>,
+        args: &Bound<'_, PyTuple>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Py<PyAny>> {
+        let target = self.target.bind(py);
+        let mut missing: Vec<&Param> = Vec::new();
+        for p in &self.params {
+            let positional = p.position.is_some_and(|i| i < args.len());
+            if !positional && !kwargs.map_or(Ok(false), |k| k.contains(p.name.bind(py)))? {
+                missing.push(p);
+            }
+        }
+        if missing.is_empty() {
+            return Ok(target.call(args, kwargs)?.unbind());
+        }
+
+        // The plan covers every dependency parameter; partial calls resolve
+        // only the missing keys
+        let values = if missing.len() == self.params.len() {
+            self.plan.execute(py)?
+        } else {
+            self.resolve_partial(py, &missing)?
+        };
+        let mut positional: Vec<Bound<'_, PyAny>> = args.iter().collect();
+        let kw = match kwargs {
+            Some(k) => k.copy()?,
+            None => PyDict::new(py),
+        };
+        for (p, value) in missing.into_iter().zip(values) {
+            if p.keyword {
+                kw.set_item(p.name.bind(py), value)?;
+            } else if p.position == Some(positional.len()) {
+                positional.push(value.into_bound(py));
+            } else {
+                return Err(PyTypeError::new_err(format!(
+                    "Cannot inject positional-only parameter '{}': earlier positional \
+                     arguments are missing",
+                    p.name.bind(py)
+                )));
+            }
+        }
+        Ok(target.call(PyTuple::new(py, positional)?, Some(&kw))?.unbind())
+    }
+
+    /// Bind to `instance` like a function does.
+    fn __get__(
+        slf: Bound<'_, Self>,
+        instance: Option<Bound<'_, PyAny>>,
+        _owner: Option<Bound<'_, PyAny>>,
+    ) -> PyResult<Py<PyAny>> {
+        let py = slf.py();
+        match instance.filter(|i| !i.is_none()) {
+            Some(instance) => {
+                let method = METHOD_TYPE.import(py, "types", "MethodType")?;
+                Ok(method.call1((slf, instance))?.unbind())
+            }
+            None => Ok(slf.into_any().unbind()),
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! Key interning.
+//!
+//! Keys are interned into dense integer ids when a provider is registered,
+//! dependency keys included. The provider tables are vectors indexed by id,
+//! so resolution and plan compilation hash a key once at the entry point and
+//! then work on ids; key strings are only needed for error messages.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::Provider;
+
+/// Interned key.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub(crate) struct KeyId(u32);
+
+impl KeyId {
+    pub(crate) fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Key strings and their ids; ids are never reused.
+#[derive(Clone, Default)]
+pub(crate) struct Interner {
+    ids: HashMap<Arc<str>, KeyId>,
+    names: Vec<Arc<str>>,
+}
+
+impl Interner {
+    pub(crate) fn intern(&mut self, key: &str) -> KeyId {
+        if let Some(&id) = self.ids.get(key) {
+            return id;
+        }
+        let id = KeyId(u32::try_from(self.names.len()).expect("too many keys"));
+        let name: Arc<str> = Arc::from(key);
+        self.names.push(name.clone());
+        self.ids.insert(name, id);
+        id
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub(crate) fn id(&self, key: &str) -> Option<KeyId> {
+        self.ids.get(key).copied()
+    }
+
+    pub(crate) fn name(&self, id: KeyId) -> &Arc<str> {
+        &self.names[id.index()]
+    }
+
+    /// Key strings of `ids`, e.g. a dependency path for an error message.
+    pub(crate) fn names(&self, ids: &[KeyId]) -> Vec<String> {
+        ids.iter().map(|&id| self.name(id).to_string()).collect()
+    }
+}
+
+/// Provider entries indexed by key id.
+#[derive(Default)]
+pub(crate) struct Table {
+    slots: Vec<Option<Arc<Provider>>>,
+}
+
+impl Table {
+    pub(crate) fn get(&self, id: KeyId) -> Option<&Arc<Provider>> {
+        self.slots.get(id.index())?.as_ref()
+    }
+
+    pub(crate) fn contains(&self, id: KeyId) -> bool {
+        self.get(id).is_some()
+    }
+
+    pub(crate) fn insert(&mut self, id: KeyId, provider: Arc<Provider>) -> Option<Arc<Provider>> {
+        if self.slots.len() <= id.index() {
+            self.slots.resize(id.index() + 1, None);
+        }
+        self.slots[id.index()].replace(provider)
+    }
+
+    pub(crate) fn remove(&mut self, id: KeyId) -> Option<Arc<Provider>> {
+        self.slots.get_mut(id.index())?.take()
+    }
+
+    /// Entries in id (first registration) order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (KeyId, &Arc<Provider>)> {
+        self.slots.iter().enumerate().filter_map(|(i, p)| Some((KeyId(i as u32), p.as_ref()?)))
+    }
+
+    pub(crate) fn values(&self) -> impl Iterator<Item = &Arc<Provider>> {
+        self.slots.iter().flatten()
+    }
+
+    pub(crate) fn into_values(self) -> impl Iterator<Item = Arc<Provider>> {
+        self.slots.into_iter().flatten()
+    }
+}
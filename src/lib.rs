@@ -1,384 +1,1026 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::ffi::CString;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
 use pyo3::prelude::*;
-use pyo3::exceptions::{PyKeyError, PyRuntimeError};
-use pyo3::types::PyTuple;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::intern;
+
+mod aio;
+mod args;
+mod errors;
+mod flight;
+mod frozen;
+mod graph;
+mod inject;
+mod intern;
+mod plan;
+mod scope;
+mod teardown;
+mod ttl;
+mod validate;
+
+use aio::Awaitable;
+use args::Args;
+use flight::{Actor, Flight, FlightGuard};
+use frozen::Frozen;
+use inject::Injector;
+use intern::{Interner, KeyId, Table};
+use plan::{AsyncPlanRun, CompiledPlan, Plan, PlanNode};
+use scope::Scope;
+use teardown::{AsyncTeardown, Finalizer, Kind};
+use ttl::{Clock, Expiry, Ttl};
+use validate::{Problem, ValidationReport};
+
+/// How long a produced value is reused.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Lifetime {
+    // Recomputed on every resolution
+    Transient,
+    // Cached in the active `Scope`; transient when no scope is active
+    Request,
+    // Cached on the provider entry
+    Singleton,
+}
+
+impl Lifetime {
+    fn parse(singleton: bool, scope: Option<&str>) -> PyResult<Self> {
+        if singleton {
+            return Ok(Lifetime::Singleton);
+        }
+        match scope {
+            None | Some("transient") => Ok(Lifetime::Transient),
+            Some("request") => Ok(Lifetime::Request),
+            Some("singleton") => Ok(Lifetime::Singleton),
+            Some(other) => Err(PyValueError::new_err(format!(
+                "Unknown scope '{}'; expected 'transient', 'request' or 'singleton'",
+                other
+            ))),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Lifetime::Transient => "transient",
+            Lifetime::Request => "request",
+            Lifetime::Singleton => "singleton",
+        }
+    }
+}
+
+/// How compilation treats captive dependencies: singletons depending on
+/// shorter-lived (request-scoped or transient) providers.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Captive {
+    Ignore,
+    // Emit a `CaptiveDependencyWarning` once per edge
+    Warn,
+    // Raise `CaptiveDependency`
+    Error,
+}
+
+impl Captive {
+    fn parse(mode: Option<&str>) -> PyResult<Self> {
+        match mode {
+            Some("ignore") => Ok(Captive::Ignore),
+            None | Some("warn") => Ok(Captive::Warn),
+            Some("error") => Ok(Captive::Error),
+            Some(other) => Err(PyValueError::new_err(format!(
+                "Unknown captive dependency mode '{}'; expected 'error', 'warn' or 'ignore'",
+                other
+            ))),
+        }
+    }
+}
 
 #[derive(Clone)]
 struct ProviderMeta {
-    singleton: bool,
+    lifetime: Lifetime,
+    kind: Kind,
     is_async: bool,
-    dep_keys: Vec<String>,
+    // Interned dependency keys, in argument order
+    deps: Box<[KeyId]>,
+    // Singletons only: cached values are rebuilt once it elapses
+    ttl: Option<Ttl>,
+    // Singletons only: built by `warmup()` / `awarmup()` without explicit keys
+    eager: bool,
 }
 
-struct Provider {
-    callable: Py<PyAny>,
-    meta: ProviderMeta,
-    cache: Option<Py<PyAny>>, // only used when singleton=true
+impl ProviderMeta {
+    fn new(
+        lifetime: Lifetime,
+        kind: Kind,
+        is_async: bool,
+        deps: Box<[KeyId]>,
+        ttl: Option<Ttl>,
+        eager: bool,
+    ) -> PyResult<Self> {
+        if ttl.is_some() && lifetime != Lifetime::Singleton {
+            return Err(PyValueError::new_err("ttl is only supported for singletons"));
+        }
+        if eager && lifetime != Lifetime::Singleton {
+            return Err(PyValueError::new_err("eager is only supported for singletons"));
+        }
+        // Async generators and async context managers are entered by awaiting
+        let is_async = is_async || kind.is_async();
+        Ok(Self { lifetime, kind, is_async, deps, ttl, eager })
+    }
 }
 
-impl Provider {
-    fn new(callable: Py<PyAny>, singleton: bool, is_async: bool, dep_keys: Vec<String>) -> Self {
-        Self { callable, meta: ProviderMeta { singleton, is_async, dep_keys }, cache: None }
+// Creation counter for cached singletons; teardown runs newest first
+static CREATED: AtomicU64 = AtomicU64::new(0);
+
+/// A cached singleton value and the teardown recorded when it was produced.
+struct Cached {
+    value: Py<PyAny>,
+    finalizer: Option<Finalizer>,
+    seq: u64,
+    expiry: Expiry,
+}
+
+impl Cached {
+    fn new(value: Py<PyAny>, finalizer: Option<Finalizer>, expiry: Expiry) -> Self {
+        Self { value, finalizer, seq: CREATED.fetch_add(1, Ordering::Relaxed), expiry }
     }
 }
 
-fn clone_py(py: Python<'_>, value: &Py<PyAny>) -> Py<PyAny> {
-    value.clone_ref(py)
+/// Singleton cache slot: the cached value, or the initialization in progress.
+#[derive(Default)]
+struct Slot {
+    value: Option<Cached>,
+    flight: Option<Arc<Flight>>,
+    // Expired values whose teardown has not run yet
+    expired: Vec<Cached>,
 }
 
-fn clone_cache(py: Python<'_>, cache: &Option<Py<PyAny>>) -> Option<Py<PyAny>> {
-    cache.as_ref().map(|v| v.clone_ref(py))
+/// Outcome of claiming a provider before computing its value.
+enum Claim {
+    // Cached for the provider's lifetime
+    Cached(Py<PyAny>),
+    // The caller computes the value; for singletons it holds the flight
+    Build(Option<FlightGuard>),
+    // Another caller is initializing the singleton
+    Wait(Arc<Flight>),
 }
 
-struct ContainerInner {
-    providers: HashMap<String, Provider>,
-    // Stack of override layers; last is topmost
-    overrides: Vec<HashMap<String, Provider>>,
+/// A registered provider. Shared (`Arc`) between the registration tables and
+/// compiled plans, so the singleton cache lives behind its own lock. Cache
+/// hits only take it for reading, so they proceed in parallel on free-threaded
+/// Python.
+struct Provider {
+    // Interned key the provider is registered under
+    id: KeyId,
+    callable: Py<PyAny>,
+    meta: ProviderMeta,
+    // Called with each produced value on teardown
+    disposer: Option<Py<PyAny>>,
+    cache: RwLock<Slot>, // only used for singletons
+    // The container's clock, for cache expiry
+    clock: Arc<Clock>,
 }
 
-impl ContainerInner {
-    fn new() -> Self {
-        Self { providers: HashMap::new(), overrides: Vec::new() }
+impl Provider {
+    fn new(
+        id: KeyId,
+        callable: Py<PyAny>,
+        meta: ProviderMeta,
+        disposer: Option<Py<PyAny>>,
+        clock: Arc<Clock>,
+    ) -> Self {
+        Self { id, callable, meta, disposer, cache: RwLock::new(Slot::default()), clock }
     }
 
-    fn push_layer(&mut self) {
-        self.overrides.push(HashMap::new());
+    /// Whether produced values have a finalizer.
+    fn has_teardown(&self) -> bool {
+        self.meta.kind.has_teardown() || self.disposer.is_some()
     }
 
-    fn pop_layer(&mut self) {
-        self.overrides.pop();
+    fn is_singleton(&self) -> bool {
+        self.meta.lifetime == Lifetime::Singleton
     }
 
-    fn set_override(&mut self, key: String, provider: Provider) {
-        if let Some(top) = self.overrides.last_mut() {
-            top.insert(key, provider);
+    /// Whether resolving this provider uses the active scope, either as its
+    /// cache or as the owner of its teardown.
+    fn needs_scope(&self) -> bool {
+        match self.meta.lifetime {
+            Lifetime::Request => true,
+            Lifetime::Transient => self.has_teardown(),
+            Lifetime::Singleton => false,
         }
     }
 
-    fn register(&mut self, key: String, provider: Provider) {
-        self.providers.insert(key, provider);
+    /// The cached singleton, unless it expired.
+    fn cached(&self, py: Python<'_>) -> Option<Py<PyAny>> {
+        let (value, expiry) = {
+            let slot = self.cache.read().unwrap();
+            let cached = slot.value.as_ref()?;
+            (cached.value.clone_ref(py), cached.expiry)
+        };
+        // The clock is read outside the lock: a custom clock runs Python code
+        if expiry.at.is_some() && expiry.expired(self.clock.now(py)) {
+            return None;
+        }
+        Some(value)
     }
 
-    fn resolve_many(&mut self, py: Python<'_>, keys: &[String]) -> PyResult<Vec<Py<PyAny>>> {
-        let mut out = Vec::with_capacity(keys.len());
-        for k in keys {
-            let mut seen = HashSet::new();
-            out.push(self.resolve_key(py, k, &mut seen)?);
+    /// The cached singleton, if it never expires.
+    fn permanent(&self, py: Python<'_>) -> Option<Py<PyAny>> {
+        let slot = self.cache.read().unwrap();
+        let cached = slot.value.as_ref().filter(|c| c.expiry.at.is_none())?;
+        Some(cached.value.clone_ref(py))
+    }
+
+    /// Take the cached singleton and the expired values awaiting teardown.
+    fn take_cached(&self) -> Vec<Cached> {
+        let mut slot = self.cache.write().unwrap();
+        let mut taken = std::mem::take(&mut slot.expired);
+        taken.extend(slot.value.take());
+        taken
+    }
+
+    /// Whether the teardown of a cached or expired singleton must be awaited.
+    fn has_async_teardown(&self) -> bool {
+        let slot = self.cache.read().unwrap();
+        slot.value
+            .iter()
+            .chain(&slot.expired)
+            .any(|c| c.finalizer.as_ref().is_some_and(Finalizer::is_async))
+    }
+
+    /// Deadline this provider's cached value passes on to the singletons
+    /// built from it.
+    fn inherited(&self) -> Option<f64> {
+        self.cache.read().unwrap().value.as_ref()?.expiry.inherited
+    }
+
+    /// Earliest deadline passed on by `deps` to a singleton built from them.
+    fn inherited_from<'a>(&self, deps: impl IntoIterator<Item = &'a Arc<Provider>>) -> Option<f64> {
+        if !self.is_singleton() {
+            return None;
         }
-        Ok(out)
+        deps.into_iter().map(|d| d.inherited()).fold(None, ttl::earliest)
     }
 
-    fn resolve_key(
-        &mut self,
-        py: Python<'_>,
-        key: &str,
-        seen: &mut HashSet<String>,
-    ) -> PyResult<Py<PyAny>> {
-        if !seen.insert(key.to_string()) {
-            return Err(PyRuntimeError::new_err(format!(
-                "Dependency cycle detected at key: {}",
-                key
-            )));
+    /// Expiry of a singleton value produced now.
+    fn expiry(&self, py: Python<'_>, inherited: Option<f64>) -> Expiry {
+        if self.meta.ttl.is_none() && inherited.is_none() {
+            return Expiry::default();
         }
+        Expiry::new(self.clock.now(py), self.meta.ttl, inherited)
+    }
 
-        // Find provider in overrides (topmost first) or base providers
-        // Extract call metadata without holding the mutable borrow across recursion
-        let mut maybe_meta: Option<(Py<PyAny>, ProviderMeta)> = None;
-
-        // search overrides
-        for layer in self.overrides.iter_mut().rev() {
-            if let Some(p) = layer.get_mut(key) {
-                // If singleton and cached -> return immediately
-                if p.meta.singleton {
-                    if let Some(cached) = clone_cache(py, &p.cache) {
-                        seen.remove(key);
-                        return Ok(cached);
-                    }
-                }
-                maybe_meta = Some((clone_py(py, &p.callable), p.meta.clone()));
-                break;
+    /// Claim the right to compute this provider's value.
+    ///
+    /// Singletons are initialized once: the first caller gets the flight
+    /// (owned by the running task when `is_async`, i.e. it may suspend while
+    /// building), later callers wait for it.
+    fn claim(self: &Arc<Self>, py: Python<'_>, scope: Option<&Scope>, is_async: bool) -> Claim {
+        if !self.is_singleton() {
+            return match self.lookup(py, scope) {
+                Some(cached) => Claim::Cached(cached),
+                None => Claim::Build(None),
+            };
+        }
+        if let Some(cached) = self.cached(py) {
+            return Claim::Cached(cached);
+        }
+        // Read before locking: a custom clock runs Python code
+        let now = self.clock.now(py);
+        let owner = Actor::current(py, is_async);
+        // Dropped after the lock is released, for the same reason
+        let mut _retired = None;
+        // Check again under the write lock: another caller may have won
+        let mut slot = self.cache.write().unwrap();
+        if let Some(cached) = &slot.value {
+            if !cached.expiry.expired(now) {
+                return Claim::Cached(cached.value.clone_ref(py));
+            }
+            // Expired: rebuild it; its teardown runs once the new value is stored
+            match slot.value.take() {
+                Some(old) if old.finalizer.is_some() => slot.expired.push(old),
+                old => _retired = old,
             }
         }
+        if let Some(flight) = &slot.flight {
+            return Claim::Wait(flight.clone());
+        }
+        let flight = Arc::new(Flight::new(owner));
+        slot.flight = Some(flight.clone());
+        Claim::Build(Some(FlightGuard::new(self.clone(), flight)))
+    }
 
-        if maybe_meta.is_none() {
-            if let Some(p) = self.providers.get_mut(key) {
-                if p.meta.singleton {
-                    if let Some(cached) = clone_cache(py, &p.cache) {
-                        seen.remove(key);
-                        return Ok(cached);
-                    }
-                }
-                maybe_meta = Some((clone_py(py, &p.callable), p.meta.clone()));
+    /// [`Provider::claim`] for the sync paths; blocks while another caller
+    /// initializes the singleton, so it never returns `Claim::Wait`.
+    fn claim_sync(
+        self: &Arc<Self>,
+        py: Python<'_>,
+        key: &str,
+        scope: Option<&Scope>,
+    ) -> PyResult<Claim> {
+        loop {
+            match self.claim(py, scope, false) {
+                Claim::Wait(flight) => flight.wait(py, key)?,
+                claim => return Ok(claim),
             }
         }
+    }
+
+    fn clear_flight(&self, flight: &Arc<Flight>) {
+        let mut slot = self.cache.write().unwrap();
+        if slot.flight.as_ref().is_some_and(|f| Arc::ptr_eq(f, flight)) {
+            slot.flight = None;
+        }
+    }
 
-        let (callable, meta) = maybe_meta.ok_or_else(|| {
-            PyKeyError::new_err(format!("No provider registered for key: {}", key))
-        })?;
+    /// Cache `value` if this provider is a singleton, tearing down the value it
+    /// replaces; returns whether it was stored.
+    fn store(&self, py: Python<'_>, value: Py<PyAny>) -> PyResult<bool> {
+        if !self.is_singleton() {
+            return Ok(false);
+        }
+        let cached = Cached::new(value, None, self.expiry(py, None));
+        // Tear down the previous value outside the lock: finalizers run Python code
+        let previous = self.cache.write().unwrap().value.replace(cached);
+        if let Some(finalizer) = previous.and_then(|c| c.finalizer) {
+            finalizer.run(py)?;
+        }
+        Ok(true)
+    }
 
-        // Disallow async provider in sync resolution path
-        if meta.is_async {
-            return Err(PyRuntimeError::new_err(format!(
-                "Provider for key '{}' is async and requires async resolution",
+    /// Fail before calling the provider if its value needs a teardown owner
+    /// and no scope is active.
+    fn check_owner(&self, key: &str, scope: Option<&Scope>) -> PyResult<()> {
+        if scope.is_none() && !self.is_singleton() && self.has_teardown() {
+            let message = format!(
+                "Provider for key '{}' has teardown and must be resolved within a request scope",
                 key
-            )));
+            );
+            return Err(errors::scope_error(Some(key), message));
         }
+        Ok(())
+    }
 
-        // Resolve dependencies recursively
-        let mut args: Vec<Py<PyAny>> = Vec::with_capacity(meta.dep_keys.len());
-        for dep_key in &meta.dep_keys {
-            let v = self.resolve_key(py, dep_key, seen)?;
-            args.push(v);
+    /// Call a sync provider with its dependency values; returns the injected
+    /// value. `inherited` is the deadline passed on by the dependencies.
+    fn call(
+        &self,
+        py: Python<'_>,
+        key: &str,
+        scope: Option<&Scope>,
+        args: Args,
+        inherited: Option<f64>,
+    ) -> PyResult<Py<PyAny>> {
+        self.check_owner(key, scope)?;
+        let produced = args.call(self.callable.bind(py))?;
+        self.remember(py, key, scope, produced, inherited)
+    }
+
+    /// Cached value for this provider's lifetime, if any.
+    fn lookup(&self, py: Python<'_>, scope: Option<&Scope>) -> Option<Py<PyAny>> {
+        match self.meta.lifetime {
+            Lifetime::Singleton => self.cached(py),
+            Lifetime::Request => scope.and_then(|s| s.get(py, self.id)),
+            Lifetime::Transient => None,
         }
+    }
 
-        // Call provider
-        let bound_args: Vec<_> = args.iter().map(|a| a.bind(py)).collect();
-        let arg_tuple = PyTuple::new(py, &bound_args)?;
-        let produced = callable.bind(py).call1(arg_tuple)?;
-        let produced_owned: Py<PyAny> = produced.into();
-
-        // Store in cache if singleton
-        if meta.singleton {
-            let cache_value = produced_owned.clone_ref(py);
-            // Assign cache into the appropriate map
-            // Try overrides first
-            for layer in self.overrides.iter_mut().rev() {
-                if let Some(p) = layer.get_mut(key) {
-                    if p.meta.singleton {
-                        p.cache = Some(cache_value.clone_ref(py));
-                        seen.remove(key);
-                        return Ok(produced_owned);
+    /// Turn a provider's return value into the injected value and cache it
+    /// according to this provider's lifetime. Its finalizer is kept next to
+    /// the cached singleton, or by the scope otherwise.
+    fn remember(
+        &self,
+        py: Python<'_>,
+        key: &str,
+        scope: Option<&Scope>,
+        produced: Bound<'_, PyAny>,
+        inherited: Option<f64>,
+    ) -> PyResult<Py<PyAny>> {
+        let (value, finalizer) = self.meta.kind.enter(py, key, produced)?;
+        self.keep(py, scope, value, finalizer, inherited)
+    }
+
+    /// Cache an already entered value; see [`Provider::remember`].
+    fn keep(
+        &self,
+        py: Python<'_>,
+        scope: Option<&Scope>,
+        value: Py<PyAny>,
+        finalizer: Option<Finalizer>,
+        inherited: Option<f64>,
+    ) -> PyResult<Py<PyAny>> {
+        let finalizer = finalizer.or_else(|| {
+            let disposer = self.disposer.as_ref()?.clone_ref(py);
+            Some(Finalizer::Dispose { disposer, value: value.clone_ref(py) })
+        });
+        match self.meta.lifetime {
+            Lifetime::Singleton => {
+                let expiry = self.expiry(py, inherited);
+                let mut slot = self.cache.write().unwrap();
+                if let Some(existing) = slot.value.as_ref() {
+                    // A value was set meanwhile (`set_cached`); keep it and tear down ours
+                    let existing = existing.value.clone_ref(py);
+                    drop(slot);
+                    if let Some(finalizer) = finalizer {
+                        finalizer.run(py)?;
                     }
+                    return Ok(existing);
                 }
+                slot.value = Some(Cached::new(value.clone_ref(py), finalizer, expiry));
+                // Tear down the values this one replaces once they expired;
+                // async teardown waits for the container's `aclose()`
+                let (later, now): (Vec<Cached>, Vec<Cached>) = std::mem::take(&mut slot.expired)
+                    .into_iter()
+                    .partition(|c| c.finalizer.as_ref().is_some_and(Finalizer::is_async));
+                slot.expired = later;
+                drop(slot);
+                teardown::run_all(py, now.into_iter().filter_map(|c| c.finalizer))?;
             }
-            if let Some(p) = self.providers.get_mut(key) {
-                if p.meta.singleton {
-                    p.cache = Some(cache_value);
+            Lifetime::Request | Lifetime::Transient => {
+                if let Some(s) = scope {
+                    if self.meta.lifetime == Lifetime::Request {
+                        s.insert(self.id, value.clone_ref(py));
+                    }
+                    if let Some(finalizer) = finalizer {
+                        s.push_finalizer(finalizer);
+                    }
                 }
             }
         }
-
-        seen.remove(key);
-        Ok(produced_owned)
+        Ok(value)
     }
+}
 
-    fn get_provider_meta(&mut self, py: Python<'_>, key: &str) -> Option<(Py<PyAny>, ProviderMeta)> {
-        // search overrides topmost first
-        for layer in self.overrides.iter_mut().rev() {
-            if let Some(p) = layer.get_mut(key) {
-                return Some((clone_py(py, &p.callable), p.meta.clone()));
-            }
+/// Take the cached singletons of `providers`, newest first.
+fn take_all(providers: impl IntoIterator<Item = Arc<Provider>>) -> Vec<Cached> {
+    let mut cached: Vec<Cached> = providers.into_iter().flat_map(|p| p.take_cached()).collect();
+    cached.sort_by_key(|c| std::cmp::Reverse(c.seq));
+    cached
+}
+
+/// Container shutdown steps for `cached`: each value's finalizer, or its
+/// `close()` / `aclose()` method.
+fn shutdown_steps(cached: Vec<Cached>) -> Vec<Finalizer> {
+    cached.into_iter().map(|c| c.finalizer.unwrap_or(Finalizer::Close(c.value))).collect()
+}
+
+fn async_teardown_pending() -> PyErr {
+    PyRuntimeError::new_err("Container has async teardown pending; use aclose()")
+}
+
+fn clone_py(py: Python<'_>, value: &Py<PyAny>) -> Py<PyAny> {
+    value.clone_ref(py)
+}
+
+struct ContainerInner {
+    // Interned keys; the provider tables are indexed by key id
+    interner: Interner,
+    providers: Table,
+    // Stack of override layers; last is topmost
+    overrides: Vec<Table>,
+    // Observability callback: hook(event, payload)
+    hook: Option<Py<PyAny>>,
+    // Singletons of popped override layers whose teardown must be awaited;
+    // `aclose()` runs them
+    deferred: Vec<Cached>,
+    // Roots of plans compiled for sync-only consumers (`@inject`), checked by
+    // `validate()`
+    sync_roots: BTreeSet<String>,
+}
+
+impl ContainerInner {
+    fn new() -> Self {
+        Self {
+            interner: Interner::default(),
+            providers: Table::default(),
+            overrides: Vec::new(),
+            hook: None,
+            deferred: Vec::new(),
+            sync_roots: BTreeSet::new(),
         }
-        if let Some(p) = self.providers.get_mut(key) {
-            return Some((clone_py(py, &p.callable), p.meta.clone()));
+    }
+
+    /// Intern `key` and the dependency keys of a provider about to be
+    /// registered under it.
+    fn intern(&mut self, key: &str, dep_keys: &[String]) -> (KeyId, Box<[KeyId]>) {
+        let deps = dep_keys.iter().map(|d| self.interner.intern(d)).collect();
+        (self.interner.intern(key), deps)
+    }
+
+    fn push_layer(&mut self) {
+        self.overrides.push(Table::default());
+    }
+
+    fn pop_layer(&mut self) -> Option<Table> {
+        self.overrides.pop()
+    }
+
+    fn set_override(&mut self, provider: Provider) {
+        if let Some(top) = self.overrides.last_mut() {
+            top.insert(provider.id, Arc::new(provider));
         }
-        None
     }
 
-    fn compile_order(&mut self, py: Python<'_>, roots: &[String]) -> PyResult<Vec<String>> {
-        // DFS for topological order
-        let mut state: HashMap<String, u8> = HashMap::new();
-        let mut order: Vec<String> = Vec::new();
+    /// Register `provider` under its key; returns the cached values built
+    /// from the provider it replaces, if any (see `take_stale`).
+    fn register(&mut self, provider: Provider) -> Vec<Cached> {
+        let stale = self.take_stale(provider.id);
+        self.providers.insert(provider.id, Arc::new(provider));
+        stale
+    }
 
-        fn visit(
-            me: &mut ContainerInner,
-            py: Python<'_>,
-            k: &str,
-            state: &mut HashMap<String, u8>,
-            order: &mut Vec<String>,
-        ) -> PyResult<()> {
-            match state.get(k).copied() {
-                Some(1) => {
-                    return Err(PyRuntimeError::new_err(format!(
-                        "Dependency cycle detected at key: {}",
-                        k
-                    )))
+    /// Remove the base registration of `key`; returns the cached values built
+    /// from it (see `take_stale`).
+    fn unregister(&mut self, key: &str) -> PyResult<Vec<Cached>> {
+        let id = self.interner.id(key).filter(|&id| self.providers.contains(id));
+        let id = id.ok_or_else(|| errors::provider_not_found(key, &[]))?;
+        let stale = self.take_stale(id);
+        self.providers.remove(id);
+        Ok(stale)
+    }
+
+    /// Take the cached value of the base registration of `id` and of every
+    /// singleton transitively depending on it, newest first.
+    fn take_stale(&self, id: KeyId) -> Vec<Cached> {
+        let base = self.providers.get(id).cloned();
+        take_all(base.into_iter().chain(self.dependent_entries(id)))
+    }
+
+    /// Every entry, in any layer, whose key transitively depends on `id`.
+    ///
+    /// Dependents are followed through every entry, shadowed ones included, so
+    /// that no value built from an old provider survives an override layer
+    /// being popped.
+    fn dependent_entries(&self, id: KeyId) -> Vec<Arc<Provider>> {
+        let layers = std::iter::once(&self.providers).chain(&self.overrides);
+        let entries: Vec<(KeyId, &Arc<Provider>)> = layers.flat_map(|l| l.iter()).collect();
+        let mut stale = vec![false; self.interner.len()];
+        stale[id.index()] = true;
+        let mut queue = vec![id];
+        while let Some(changed) = queue.pop() {
+            for (k, p) in &entries {
+                if !stale[k.index()] && p.meta.deps.contains(&changed) {
+                    stale[k.index()] = true;
+                    queue.push(*k);
                 }
-                Some(2) => return Ok(()),
-                _ => {}
             }
-            state.insert(k.to_string(), 1);
-            let (_, meta) = me
-                .get_provider_meta(py, k)
-                .ok_or_else(|| PyKeyError::new_err(format!("No provider registered for key: {}", k)))?;
-            if meta.is_async {
-                return Err(PyRuntimeError::new_err(format!(
-                    "Provider for key '{}' is async and requires async resolution",
-                    k
+        }
+        entries
+            .into_iter()
+            .filter(|(k, _)| *k != id && stale[k.index()])
+            .map(|(_, p)| p.clone())
+            .collect()
+    }
+
+    /// Keys to warm up: `keys`, which must be singletons, or every eager
+    /// singleton.
+    fn warmup_roots(&self, keys: Option<Vec<String>>) -> PyResult<Vec<String>> {
+        let Some(keys) = keys else {
+            let eager = |k: &String| self.get_key(k).is_some_and(|p| p.meta.eager);
+            return Ok(self.keys().into_iter().filter(eager).collect());
+        };
+        for key in &keys {
+            let provider = self.get_key(key);
+            let provider = provider.ok_or_else(|| errors::provider_not_found(key, &[]))?;
+            if !provider.is_singleton() {
+                return Err(PyValueError::new_err(format!(
+                    "Cannot warm up key '{}': only singletons are cached",
+                    key
                 )));
             }
-            for dep in &meta.dep_keys {
-                visit(me, py, dep, state, order)?;
-            }
-            state.insert(k.to_string(), 2);
-            order.push(k.to_string());
-            Ok(())
         }
+        Ok(keys)
+    }
 
-        for r in roots {
-            visit(self, py, r, &mut state, &mut order)?;
-        }
-        Ok(order)
+    /// Take the cached values of `key` in every layer, and with `cascade` those
+    /// of the singletons transitively depending on it, newest first.
+    fn reset(&self, key: &str, cascade: bool) -> PyResult<Vec<Cached>> {
+        let id = self.interner.id(key);
+        let id = id.filter(|&id| self.get(id).is_some());
+        let id = id.ok_or_else(|| errors::provider_not_found(key, &[]))?;
+        let layers = std::iter::once(&self.providers).chain(&self.overrides);
+        let entries: Vec<Arc<Provider>> = layers.filter_map(|l| l.get(id)).cloned().collect();
+        let dependents = if cascade { self.dependent_entries(id) } else { Vec::new() };
+        Ok(take_all(entries.into_iter().chain(dependents)))
     }
 
-    fn execute_order(
-        &mut self,
-        py: Python<'_>,
-        order: &[String],
-    ) -> PyResult<HashMap<String, Py<PyAny>>> {
-        let mut computed: HashMap<String, Py<PyAny>> = HashMap::new();
-        for key in order {
-            // Check singleton caches first
-            // Determine provider location and metadata again (may have changed)
-            // Also capture deps for current key
-            let mut provider_loc_is_override = false;
-            let mut deps: Vec<String> = Vec::new();
-            let mut is_singleton = false;
-            let mut callable: Option<Py<PyAny>> = None;
-
-            // check overrides
-            for layer in self.overrides.iter_mut().rev() {
-                if let Some(p) = layer.get_mut(key.as_str()) {
-                    if p.meta.singleton {
-                        if let Some(cached) = clone_cache(py, &p.cache) {
-                            computed.insert(key.clone(), cached);
-                            callable = None;
-                            is_singleton = true; // irrelevant now
-                            deps.clear();
-                            break;
-                        }
-                    }
-                    callable = Some(clone_py(py, &p.callable));
-                    deps = p.meta.dep_keys.clone();
-                    is_singleton = p.meta.singleton;
-                    provider_loc_is_override = true;
-                    break;
-                }
-            }
-            if computed.contains_key(key) {
-                continue;
-            }
-            if callable.is_none() {
-                if let Some(p) = self.providers.get_mut(key.as_str()) {
-                    if p.meta.singleton {
-                        if let Some(cached) = clone_cache(py, &p.cache) {
-                            computed.insert(key.clone(), cached);
-                            continue;
-                        }
-                    }
-                    callable = Some(clone_py(py, &p.callable));
-                    deps = p.meta.dep_keys.clone();
-                    is_singleton = p.meta.singleton;
-                } else {
-                    return Err(PyKeyError::new_err(format!(
-                        "No provider registered for key: {}",
-                        key
-                    )));
-                }
-            }
+    /// Every entry with its key string: base registrations, then each override
+    /// layer.
+    fn entries(&self) -> impl Iterator<Item = (&str, &Arc<Provider>)> {
+        let layers = std::iter::once(&self.providers).chain(&self.overrides);
+        layers.flat_map(|l| l.iter()).map(|(id, p)| (&**self.interner.name(id), p))
+    }
 
-            // build args from computed deps
-            let mut args_vec: Vec<Py<PyAny>> = Vec::with_capacity(deps.len());
-            for d in &deps {
-                if let Some(v) = computed.get(d) {
-                    args_vec.push(clone_py(py, v));
-                } else {
-                    // Should not happen if order includes deps first
-                    let mut seen = HashSet::new();
-                    let v = self.resolve_key(py, d, &mut seen)?;
-                    args_vec.push(v);
-                }
+    /// Every provider entry: base registrations and all override layers.
+    fn all_providers(&self) -> Vec<Arc<Provider>> {
+        let layers = self.overrides.iter().flat_map(|l| l.values());
+        self.providers.values().chain(layers).cloned().collect()
+    }
+
+    /// Active provider for `id`: topmost override layer first, then base.
+    fn get(&self, id: KeyId) -> Option<&Arc<Provider>> {
+        self.overrides
+            .iter()
+            .rev()
+            .find_map(|layer| layer.get(id))
+            .or_else(|| self.providers.get(id))
+    }
+
+    /// Active provider for the key string `key`.
+    fn get_key(&self, key: &str) -> Option<&Arc<Provider>> {
+        self.get(self.interner.id(key)?)
+    }
+
+    /// Key strings of a provider's dependencies.
+    fn dep_names(&self, provider: &Provider) -> Vec<&str> {
+        provider.meta.deps.iter().map(|&d| &**self.interner.name(d)).collect()
+    }
+
+    /// Compile a topological plan for `roots`.
+    ///
+    /// Every reachable key appears exactly once, after all of its dependencies;
+    /// each node records its dependencies as indices into the node list.
+    fn compile_plan(
+        &self,
+        roots: &[String],
+        allow_async: bool,
+        generation: u64,
+    ) -> PyResult<CompiledPlan> {
+        // DFS over key ids; `Some(None)` marks a key that is still on the
+        // stack, `path` holds the stack itself for error messages
+        let mut index: Vec<Option<Option<usize>>> = vec![None; self.interner.len()];
+        let mut nodes: Vec<PlanNode> = Vec::new();
+        let mut path: Vec<KeyId> = Vec::new();
+
+        fn visit(
+            me: &ContainerInner,
+            id: KeyId,
+            allow_async: bool,
+            index: &mut [Option<Option<usize>>],
+            nodes: &mut Vec<PlanNode>,
+            path: &mut Vec<KeyId>,
+        ) -> PyResult<usize> {
+            let key = me.interner.name(id);
+            match index[id.index()] {
+                Some(Some(i)) => return Ok(i),
+                Some(None) => return Err(errors::dependency_cycle(key, &me.interner.names(path))),
+                None => {}
             }
-            let bound_args: Vec<_> = args_vec.iter().map(|a| a.bind(py)).collect();
-            let arg_tuple = PyTuple::new(py, &bound_args)?;
-            let produced = callable.unwrap().bind(py).call1(arg_tuple)?;
-            let produced_owned: Py<PyAny> = produced.into();
-
-            // cache if singleton
-            if is_singleton {
-                let cache_value = produced_owned.clone_ref(py);
-                if provider_loc_is_override {
-                    for layer in self.overrides.iter_mut().rev() {
-                        if let Some(p) = layer.get_mut(key.as_str()) {
-                            if p.meta.singleton {
-                                p.cache = Some(cache_value.clone_ref(py));
-                                break;
-                            }
-                        }
-                    }
-                } else if let Some(p) = self.providers.get_mut(key.as_str()) {
-                    if p.meta.singleton {
-                        p.cache = Some(cache_value);
-                    }
-                }
+            index[id.index()] = Some(None);
+            let provider = me.get(id).cloned();
+            let provider = provider
+                .ok_or_else(|| errors::provider_not_found(key, &me.interner.names(path)))?;
+            if provider.meta.is_async && !allow_async {
+                return Err(errors::async_in_sync(key, &me.interner.names(path)));
+            }
+            path.push(id);
+            let mut deps = Vec::with_capacity(provider.meta.deps.len());
+            for &dep in provider.meta.deps.iter() {
+                deps.push(visit(me, dep, allow_async, index, nodes, path)?);
             }
+            path.pop();
+            nodes.push(PlanNode { key: key.to_string(), provider, deps });
+            let i = nodes.len() - 1;
+            index[id.index()] = Some(Some(i));
+            Ok(i)
+        }
 
-            computed.insert(key.clone(), produced_owned);
+        let mut root_ids = Vec::with_capacity(roots.len());
+        for r in roots {
+            let id = self.interner.id(r).ok_or_else(|| errors::provider_not_found(r, &[]))?;
+            root_ids.push(visit(self, id, allow_async, &mut index, &mut nodes, &mut path)?);
         }
-        Ok(computed)
+        Ok(CompiledPlan::new(generation, nodes, root_ids))
     }
 }
 
-#[pyclass]
+#[pyclass(frozen)]
 struct Container {
-    inner: std::sync::Mutex<ContainerInner>,
+    // Read-locked by resolution and compilation, write-locked by registration
+    // and override changes
+    inner: RwLock<ContainerInner>,
+    // Bumped on every registration/override change; compiled plans compare
+    // against it to detect that they are stale.
+    generation: AtomicU64,
+    // contextvars.ContextVar holding the active request `Scope`
+    scope_var: Py<PyAny>,
+    // Set by close()/aclose(); resolution is rejected afterwards
+    closed: AtomicBool,
+    // Raise provider failures as `ResolutionError` with the dependency path
+    wrap_errors: bool,
+    captive: Captive,
+    // Captive edges (singleton, dependency) already warned about
+    warned: Mutex<HashSet<(String, String)>>,
+    // Time source for singleton TTLs
+    clock: Arc<Clock>,
+    // Set by freeze(); resolution then reads it without locking
+    frozen: OnceLock<Frozen>,
 }
 
 #[pymethods]
 impl Container {
     #[new]
-    fn new() -> Self {
-        Self { inner: std::sync::Mutex::new(ContainerInner::new()) }
+    #[pyo3(signature = (wrap_errors=false, captive=None, clock=None))]
+    fn new(
+        py: Python<'_>,
+        wrap_errors: bool,
+        captive: Option<&str>,
+        clock: Option<Py<PyAny>>,
+    ) -> PyResult<Self> {
+        let captive = Captive::parse(captive)?;
+        Self::with_settings(py, wrap_errors, captive, Arc::new(Clock::new(clock)))
     }
 
+    /// Turn the registrations into an immutable table resolved without
+    /// locking. Afterwards registration, overrides and cache changes raise.
+    /// Fails if a dependency has no provider or an override is active.
+    fn freeze(&self, py: Python<'_>) -> PyResult<()> {
+        // Frozen under the read lock, so no registration slips in meanwhile
+        let g = self.inner.read().unwrap();
+        if self.frozen.get().is_none() {
+            let _ = self.frozen.set(g.freeze(py)?);
+        }
+        Ok(())
+    }
+
+    /// Whether `freeze()` was called.
+    #[getter]
+    fn frozen(&self) -> bool {
+        self.frozen.get().is_some()
+    }
+
+    /// A new, unfrozen container with the same settings and registrations and
+    /// empty caches, e.g. to override providers of a frozen container in tests.
+    fn testing_copy(&self, py: Python<'_>) -> PyResult<Self> {
+        let copy = Self::with_settings(py, self.wrap_errors, self.captive, self.clock.clone())?;
+        {
+            let g = self.inner.read().unwrap();
+            let mut c = copy.inner.write().unwrap();
+            // Same ids, so the copied dependency lists stay valid
+            c.interner = g.interner.clone();
+            for p in g.providers.values() {
+                let disposer = p.disposer.as_ref().map(|d| d.clone_ref(py));
+                let provider = Provider::new(
+                    p.id,
+                    p.callable.clone_ref(py),
+                    p.meta.clone(),
+                    disposer,
+                    copy.clock.clone(),
+                );
+                c.register(provider);
+            }
+        }
+        Ok(copy)
+    }
+
+    /// Register a provider under `key`, replacing any previous registration.
+    ///
+    /// Values cached from a replaced provider, and the singletons depending on
+    /// it, are torn down so they are rebuilt from the new provider. With
+    /// `replace`, the key must already be registered.
+    ///
+    /// A singleton with a `ttl` (seconds) is rebuilt on the first resolution
+    /// after it elapses; with `expire_dependents`, the singletons built from
+    /// its value expire with it. `eager` singletons are built by `warmup()`.
+    #[pyo3(signature = (
+        key,
+        callable,
+        singleton,
+        is_async,
+        dep_keys,
+        scope=None,
+        kind=None,
+        disposer=None,
+        replace=false,
+        ttl=None,
+        expire_dependents=false,
+        eager=false
+    ))]
+    #[allow(clippy::too_many_arguments)]
     fn register_provider(
         &self,
+        py: Python<'_>,
         key: String,
         callable: Py<PyAny>,
         singleton: bool,
         is_async: bool,
         dep_keys: Vec<String>,
+        scope: Option<&str>,
+        kind: Option<&str>,
+        disposer: Option<Py<PyAny>>,
+        replace: bool,
+        ttl: Option<f64>,
+        expire_dependents: bool,
+        eager: bool,
     ) -> PyResult<()> {
-        let provider = Provider::new(callable, singleton, is_async, dep_keys);
-        let mut g = self.inner.lock().unwrap();
-        g.register(key, provider);
+        let lifetime = Lifetime::parse(singleton, scope)?;
+        let kind = Kind::parse(kind)?;
+        let ttl = Ttl::parse(ttl, expire_dependents)?;
+        let stale = {
+            let mut g = self.inner.write().unwrap();
+            self.ensure_mutable()?;
+            if replace && !g.interner.id(&key).is_some_and(|id| g.providers.contains(id)) {
+                return Err(errors::provider_not_found(&key, &[]));
+            }
+            let (id, deps) = g.intern(&key, &dep_keys);
+            let meta = ProviderMeta::new(lifetime, kind, is_async, deps, ttl, eager)?;
+            let provider = Provider::new(id, callable, meta, disposer, self.clock.clone());
+            let stale = g.register(provider);
+            self.bump_generation();
+            stale
+        };
+        self.dispose(py, stale)
+    }
+
+    /// Remove the base registration of `key`, tearing down its cached value
+    /// and the singletons depending on it.
+    fn unregister(&self, py: Python<'_>, key: &str) -> PyResult<()> {
+        let stale = {
+            let mut g = self.inner.write().unwrap();
+            self.ensure_mutable()?;
+            let stale = g.unregister(key)?;
+            self.bump_generation();
+            stale
+        };
+        self.dispose(py, stale)
+    }
+
+    /// Clear the cached singleton of `key` (base and override layers), and
+    /// with `cascade` those of its transitive dependents. Cleared values are
+    /// torn down unless `dispose` is false.
+    #[pyo3(signature = (key, cascade=false, dispose=true))]
+    fn reset(&self, py: Python<'_>, key: &str, cascade: bool, dispose: bool) -> PyResult<()> {
+        self.ensure_mutable()?;
+        let cleared = self.inner.read().unwrap().reset(key, cascade)?;
+        if dispose {
+            self.dispose(py, cleared)?;
+        }
+        Ok(())
+    }
+
+    /// Clear every cached singleton, base and override layers alike; cleared
+    /// values are torn down unless `dispose` is false.
+    #[pyo3(signature = (dispose=true))]
+    fn reset_all(&self, py: Python<'_>, dispose: bool) -> PyResult<()> {
+        self.ensure_mutable()?;
+        let cleared = take_all(self.inner.read().unwrap().all_providers());
+        if dispose {
+            self.dispose(py, cleared)?;
+        }
         Ok(())
     }
 
     fn resolve(&self, py: Python<'_>, key: String) -> PyResult<Py<PyAny>> {
-        let mut g = self.inner.lock().unwrap();
-        let mut seen = HashSet::new();
-        g.resolve_key(py, &key, &mut seen)
+        self.ensure_open()?;
+        let scope = self.active_scope(py, None)?;
+        let scope = scope.as_ref().map(|s| s.get());
+        if let Some(frozen) = self.frozen.get() {
+            return self.resolve_frozen(py, frozen, &key, scope);
+        }
+        self.resolve_key(py, &key, scope)
     }
 
     fn resolve_many(&self, py: Python<'_>, keys: Vec<String>) -> PyResult<Vec<Py<PyAny>>> {
-        let mut g = self.inner.lock().unwrap();
-        g.resolve_many(py, &keys)
+        self.ensure_open()?;
+        let scope = self.active_scope(py, None)?;
+        self.resolve_keys(py, &keys, scope.as_ref().map(|s| s.get()))
     }
 
     fn resolve_many_plan(&self, py: Python<'_>, keys: Vec<String>) -> PyResult<Vec<Py<PyAny>>> {
-        let mut g = self.inner.lock().unwrap();
-        let order = g.compile_order(py, &keys)?;
-        let computed = g.execute_order(py, &order)?;
-        let mut out = Vec::with_capacity(keys.len());
-        for k in keys.iter() {
-            if let Some(v) = computed.get(k) {
-                out.push(clone_py(py, v));
-            } else {
-                return Err(PyRuntimeError::new_err(format!(
-                    "Internal error: key {} missing after plan execution",
-                    k
-                )));
-            }
+        let compiled = self.compile_plan(&keys, false)?;
+        let scope = if compiled.needs_scope { self.active_scope(py, None)? } else { None };
+        compiled.run(py, scope.as_ref().map(|s| s.get()))
+    }
+
+    /// Instantiate the singletons `keys` (by default every `eager` one) and
+    /// their dependencies, in dependency order.
+    #[pyo3(signature = (keys=None))]
+    fn warmup(&self, py: Python<'_>, keys: Option<Vec<String>>) -> PyResult<()> {
+        let roots = self.inner.read().unwrap().warmup_roots(keys)?;
+        self.resolve_many_plan(py, roots).map(drop)
+    }
+
+    /// Async counterpart of `warmup()`: async singletons of the same level
+    /// are started concurrently. Returns an awaitable.
+    #[pyo3(signature = (keys=None))]
+    fn awarmup(&self, py: Python<'_>, keys: Option<Vec<String>>) -> PyResult<Awaitable> {
+        let roots = self.inner.read().unwrap().warmup_roots(keys)?;
+        let compiled = Arc::new(self.compile_plan(&roots, true)?);
+        self.run_async(py, compiled, None, false)
+    }
+
+    /// Create a new request scope handle for this container.
+    ///
+    /// Use it as a context manager: while entered it is the active scope of the
+    /// current thread or task, and its cached values are dropped on exit.
+    fn new_scope(slf: Py<Self>) -> Scope {
+        Scope::new(slf)
+    }
+
+    /// Compile `keys` into a reusable [`Plan`].
+    ///
+    /// Validation (missing providers, cycles and, unless `allow_async`, async
+    /// providers) happens here; the plan recompiles itself when the container
+    /// changes.
+    #[pyo3(signature = (keys, allow_async=false))]
+    fn compile(slf: Py<Self>, keys: Vec<String>, allow_async: bool) -> PyResult<Plan> {
+        let compiled = slf.get().compile_plan(&keys, allow_async)?;
+        if !allow_async {
+            slf.get().inner.write().unwrap().sync_roots.extend(keys.iter().cloned());
         }
-        Ok(out)
+        Ok(Plan::new(slf, keys, allow_async, compiled))
     }
 
-    fn begin_override_layer(&self) {
-        let mut g = self.inner.lock().unwrap();
+    /// Wrap the sync function `target` into an [`Injector`] resolving its
+    /// dependency parameters.
+    ///
+    /// `params` holds `(name, key, position, keyword)` per dependency
+    /// parameter: its positional index (`None` if keyword-only) and whether it
+    /// can be passed by keyword. Their keys are compiled like `compile()`.
+    fn injector(
+        slf: Py<Self>,
+        py: Python<'_>,
+        target: Py<PyAny>,
+        params: Vec<(String, String, Option<usize>, bool)>,
+    ) -> PyResult<Injector> {
+        let keys: Vec<String> = params.iter().map(|(_, key, _, _)| key.clone()).collect();
+        let plan = Self::compile(slf.clone_ref(py), keys, false)?;
+        Ok(Injector::new(py, slf, target, params, plan))
+    }
+
+    /// Resolve `key` asynchronously; returns an awaitable producing the value.
+    ///
+    /// `default_scope` is used for request-scoped providers when no scope is
+    /// active in the current context.
+    #[pyo3(signature = (key, default_scope=None))]
+    fn resolve_async(
+        slf: Py<Self>,
+        py: Python<'_>,
+        key: String,
+        default_scope: Option<Py<Scope>>,
+    ) -> PyResult<Awaitable> {
+        let compiled = Arc::new(slf.get().compile_plan(std::slice::from_ref(&key), true)?);
+        slf.get().run_async(py, compiled, default_scope, true)
+    }
+
+    /// Resolve `keys` asynchronously in topological order; returns an awaitable
+    /// producing a list of values in the order of `keys`.
+    #[pyo3(signature = (keys, default_scope=None))]
+    fn resolve_many_plan_async(
+        slf: Py<Self>,
+        py: Python<'_>,
+        keys: Vec<String>,
+        default_scope: Option<Py<Scope>>,
+    ) -> PyResult<Awaitable> {
+        let compiled = Arc::new(slf.get().compile_plan(&keys, true)?);
+        slf.get().run_async(py, compiled, default_scope, false)
+    }
+
+    /// Check the whole graph and report every problem at once: missing
+    /// dependencies, cycles, async providers reachable from sync consumers
+    /// (plans compiled without `allow_async`, plus `sync_roots`) and captive
+    /// dependencies (unless ignored).
+    #[pyo3(signature = (sync_roots=None))]
+    fn validate(
+        &self,
+        py: Python<'_>,
+        sync_roots: Option<Vec<String>>,
+    ) -> PyResult<ValidationReport> {
+        let problems: Vec<Problem> = {
+            let g = self.inner.read().unwrap();
+            let mut roots = g.sync_roots.clone();
+            roots.extend(sync_roots.unwrap_or_default());
+            g.validate(&roots, self.captive)
+        };
+        ValidationReport::new(py, problems)
+    }
+
+    /// Export the active dependency graph as Graphviz DOT (`"dot"`), a
+    /// Mermaid flowchart (`"mermaid"`) or a JSON node/edge list (`"json"`).
+    #[pyo3(signature = (format="mermaid"))]
+    fn export_graph(&self, py: Python<'_>, format: &str) -> PyResult<String> {
+        // Read before locking: a custom clock runs Python code
+        let now = self.clock.now(py);
+        let nodes = self.inner.read().unwrap().graph_nodes(now);
+        graph::render(py, &nodes, format)
+    }
+
+    fn set_hook(&self, hook: Option<Py<PyAny>>) {
+        let mut g = self.inner.write().unwrap();
+        g.hook = hook;
+    }
+
+    fn begin_override_layer(&self) -> PyResult<()> {
+        let mut g = self.inner.write().unwrap();
+        self.ensure_mutable()?;
         g.push_layer();
+        self.bump_generation();
+        Ok(())
     }
 
+    #[pyo3(signature = (
+        key, callable, singleton, is_async, dep_keys, scope=None, kind=None, disposer=None
+    ))]
+    #[allow(clippy::too_many_arguments)]
     fn set_override(
         &self,
         key: String,
@@ -386,10 +1028,18 @@ impl Container {
         singleton: bool,
         is_async: bool,
         dep_keys: Vec<String>,
+        scope: Option<&str>,
+        kind: Option<&str>,
+        disposer: Option<Py<PyAny>>,
     ) -> PyResult<()> {
-        let provider = Provider::new(callable, singleton, is_async, dep_keys);
-        let mut g = self.inner.lock().unwrap();
-        g.set_override(key, provider);
+        let lifetime = Lifetime::parse(singleton, scope)?;
+        let kind = Kind::parse(kind)?;
+        let mut g = self.inner.write().unwrap();
+        self.ensure_mutable()?;
+        let (id, deps) = g.intern(&key, &dep_keys);
+        let meta = ProviderMeta::new(lifetime, kind, is_async, deps, None, false)?;
+        g.set_override(Provider::new(id, callable, meta, disposer, self.clock.clone()));
+        self.bump_generation();
         Ok(())
     }
 
@@ -398,75 +1048,386 @@ impl Container {
         py: Python<'_>,
         key: String,
     ) -> PyResult<(Py<PyAny>, bool, bool, Vec<String>)> {
-        let mut g = self.inner.lock().unwrap();
-        for layer in g.overrides.iter_mut().rev() {
-            if let Some(p) = layer.get_mut(&key) {
-                return Ok((
-                    clone_py(py, &p.callable),
-                    p.meta.singleton,
-                    p.meta.is_async,
-                    p.meta.dep_keys.clone(),
-                ));
+        let g = self.inner.read().unwrap();
+        let p = g.get_key(&key).ok_or_else(|| errors::provider_not_found(&key, &[]))?;
+        let dep_keys = g.dep_names(p).into_iter().map(String::from).collect();
+        Ok((clone_py(py, &p.callable), p.is_singleton(), p.meta.is_async, dep_keys))
+    }
+
+    /// Every registered key (base registrations and override layers), sorted.
+    fn keys(&self) -> Vec<String> {
+        self.inner.read().unwrap().keys()
+    }
+
+    /// Direct (declaration order) or transitive (sorted) dependencies of `key`.
+    #[pyo3(signature = (key, transitive=false))]
+    fn dependencies(&self, key: &str, transitive: bool) -> PyResult<Vec<String>> {
+        self.inner.read().unwrap().dependencies(key, transitive)
+    }
+
+    /// Keys depending on `key`, directly or transitively, sorted.
+    #[pyo3(signature = (key, transitive=false))]
+    fn dependents(&self, key: &str, transitive: bool) -> Vec<String> {
+        self.inner.read().unwrap().dependents(key, transitive)
+    }
+
+    /// 0 when the base registration provides `key`, else the 1-based index of
+    /// the topmost override layer providing it.
+    fn provider_layer(&self, key: &str) -> PyResult<usize> {
+        self.inner.read().unwrap().provider_layer(key)
+    }
+
+    fn get_cached(&self, py: Python<'_>, key: String) -> Option<Py<PyAny>> {
+        let g = self.inner.read().unwrap();
+        g.get_key(&key).and_then(|p| p.cached(py))
+    }
+
+    fn set_cached(&self, py: Python<'_>, key: String, value: Py<PyAny>) -> PyResult<()> {
+        self.ensure_mutable()?;
+        let provider = self.inner.read().unwrap().get_key(&key).cloned();
+        if let Some(p) = provider {
+            if p.store(py, value)? {
+                return Ok(());
+            }
+        }
+        Err(PyRuntimeError::new_err(format!(
+            "Cannot set cache for non-singleton or unknown key: {}",
+            key
+        )))
+    }
+
+    /// Pop the topmost override layer and tear down its cached singletons.
+    ///
+    /// Teardown that must be awaited is deferred to `aclose()`.
+    fn end_override_layer(&self, py: Python<'_>) -> PyResult<()> {
+        let layer = {
+            let mut g = self.inner.write().unwrap();
+            let layer = g.pop_layer();
+            self.bump_generation();
+            layer
+        };
+        self.dispose(py, take_all(layer.into_iter().flat_map(|l| l.into_values())))
+    }
+
+    /// Whether `close()` or `aclose()` was called.
+    #[getter]
+    fn closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    /// Close the container: dispose of every cached singleton, base and
+    /// override layers alike, newest first, and reject further resolution.
+    ///
+    /// A value is disposed by its provider's teardown (generator, context
+    /// manager or registered disposer), else by its `close()` method. Every
+    /// value is disposed even if some fail; the failures are raised together
+    /// as a `DisposeError`. Fails without closing if some teardown must be
+    /// awaited.
+    fn close(&self, py: Python<'_>) -> PyResult<()> {
+        let providers = {
+            let g = self.inner.read().unwrap();
+            let providers = g.all_providers();
+            if !g.deferred.is_empty() || providers.iter().any(|p| p.has_async_teardown()) {
+                return Err(async_teardown_pending());
             }
+            self.closed.store(true, Ordering::Release);
+            providers
+        };
+        teardown::run_all(py, shutdown_steps(take_all(providers)))
+    }
+
+    /// Async counterpart of `close()`; returns an awaitable that awaits async
+    /// teardown and prefers a value's `aclose()` over `close()`.
+    fn aclose(&self) -> Awaitable {
+        let mut cached = {
+            let mut g = self.inner.write().unwrap();
+            self.closed.store(true, Ordering::Release);
+            let mut cached = std::mem::take(&mut g.deferred);
+            cached.extend(take_all(g.all_providers()));
+            cached
+        };
+        cached.sort_by_key(|c| std::cmp::Reverse(c.seq));
+        Awaitable::new(Box::new(AsyncTeardown::new(shutdown_steps(cached))))
+    }
+}
+
+impl Container {
+    fn ensure_open(&self) -> PyResult<()> {
+        if self.closed.load(Ordering::Acquire) {
+            return Err(PyRuntimeError::new_err("Container is closed"));
         }
-        if let Some(p) = g.providers.get_mut(&key) {
-            return Ok((
-                clone_py(py, &p.callable),
-                p.meta.singleton,
-                p.meta.is_async,
-                p.meta.dep_keys.clone(),
-            ));
+        Ok(())
+    }
+
+    fn with_settings(
+        py: Python<'_>,
+        wrap_errors: bool,
+        captive: Captive,
+        clock: Arc<Clock>,
+    ) -> PyResult<Self> {
+        let scope_var = py
+            .import("contextvars")?
+            .getattr("ContextVar")?
+            .call1(("fastdi_scope",))?
+            .unbind();
+        Ok(Self {
+            inner: RwLock::new(ContainerInner::new()),
+            generation: AtomicU64::new(0),
+            scope_var,
+            closed: AtomicBool::new(false),
+            wrap_errors,
+            captive,
+            warned: Mutex::new(HashSet::new()),
+            clock,
+            frozen: OnceLock::new(),
+        })
+    }
+
+    fn ensure_mutable(&self) -> PyResult<()> {
+        if self.frozen.get().is_some() {
+            return Err(PyRuntimeError::new_err("Container is frozen"));
         }
-        Err(PyKeyError::new_err(format!("No provider registered for key: {}", key)))
+        Ok(())
     }
 
-    fn get_cached(&self, py: Python<'_>, key: String) -> Option<Py<PyAny>> {
-        let mut g = self.inner.lock().unwrap();
-        for layer in g.overrides.iter_mut().rev() {
-            if let Some(p) = layer.get_mut(&key) {
-                if let Some(v) = clone_cache(py, &p.cache) {
-                    return Some(v);
-                }
+    fn resolve_keys(
+        &self,
+        py: Python<'_>,
+        keys: &[String],
+        scope: Option<&Scope>,
+    ) -> PyResult<Vec<Py<PyAny>>> {
+        let frozen = self.frozen.get();
+        let mut out = Vec::with_capacity(keys.len());
+        for k in keys {
+            out.push(match frozen {
+                Some(frozen) => self.resolve_frozen(py, frozen, k, scope)?,
+                None => self.resolve_key(py, k, scope)?,
+            });
+        }
+        Ok(out)
+    }
+
+    /// Resolve `key` through the container tables; see [`Container::resolve_id`].
+    fn resolve_key(&self, py: Python<'_>, key: &str, scope: Option<&Scope>) -> PyResult<Py<PyAny>> {
+        let id = self.inner.read().unwrap().interner.id(key);
+        let id = id.ok_or_else(|| errors::provider_not_found(key, &[]))?;
+        self.resolve_id(py, id, scope, &mut Vec::new())
+    }
+
+    /// Recursive resolver. The container lock is only held to look up
+    /// provider entries, never across provider calls, so providers may resolve
+    /// from the same container. `path` holds the keys being resolved, from the
+    /// root down to `id`'s dependent, for cycle detection and error messages.
+    fn resolve_id(
+        &self,
+        py: Python<'_>,
+        id: KeyId,
+        scope: Option<&Scope>,
+        path: &mut Vec<KeyId>,
+    ) -> PyResult<Py<PyAny>> {
+        // Find provider in overrides (topmost first) or base providers
+        let (provider, key) = {
+            let g = self.inner.read().unwrap();
+            let key = g.interner.name(id);
+            if path.contains(&id) {
+                return Err(errors::dependency_cycle(key, &g.interner.names(path)));
             }
+            let provider = g.get(id).cloned();
+            let provider = provider
+                .ok_or_else(|| errors::provider_not_found(key, &g.interner.names(path)))?;
+            (provider, key.clone())
+        };
+
+        // If cached (singleton or active request scope) -> return immediately
+        if let Some(cached) = provider.lookup(py, scope) {
+            return Ok(cached);
+        }
+
+        // Disallow async provider in sync resolution path
+        if provider.meta.is_async {
+            return Err(errors::async_in_sync(&key, &self.key_names(path)));
         }
-        if let Some(p) = g.providers.get_mut(&key) {
-            if let Some(v) = clone_cache(py, &p.cache) {
-                return Some(v);
+
+        // Singletons must not capture shorter-lived dependencies, as in plans
+        let captive = if self.captive == Captive::Ignore || !provider.is_singleton() {
+            Vec::new()
+        } else {
+            let g = self.inner.read().unwrap();
+            self.captive_deps(&provider, |d| Some((&**g.interner.name(d), g.get(d)?)))
+        };
+        for (dep, scope) in captive {
+            self.captive_edge(&key, &dep, scope)?;
+        }
+
+        // Resolve dependencies recursively
+        let mut args = Args::with_capacity(provider.meta.deps.len());
+        path.push(id);
+        for &dep in provider.meta.deps.iter() {
+            let v = self.resolve_id(py, dep, scope, path)?;
+            args.push(v);
+        }
+        path.pop();
+
+        // Singletons are claimed only once their dependencies are resolved, so
+        // a caller never waits for another initialization while holding one
+        let _flight = match provider.claim_sync(py, &key, scope)? {
+            Claim::Cached(cached) => return Ok(cached),
+            Claim::Build(guard) => guard,
+            Claim::Wait(_) => unreachable!("claim_sync waits for other initializations"),
+        };
+
+        // Deadline passed on by dependencies built with an expiring TTL
+        let inherited = provider.is_singleton().then(|| {
+            let g = self.inner.read().unwrap();
+            provider.inherited_from(provider.meta.deps.iter().filter_map(|&d| g.get(d)))
+        });
+
+        // Call provider; caches the value if singleton or request-scoped
+        provider.call(py, &key, scope, args, inherited.flatten()).map_err(|err| {
+            if self.wrap_errors {
+                errors::resolution_error(py, err, &key, &self.key_names(path))
+            } else {
+                err
             }
+        })
+    }
+
+    /// Key strings of a resolution path, for error messages.
+    fn key_names(&self, path: &[KeyId]) -> Vec<String> {
+        self.inner.read().unwrap().interner.names(path)
+    }
+
+    /// Run the teardown of `cached` values that are no longer reachable;
+    /// teardown that must be awaited is deferred to `aclose()`.
+    fn dispose(&self, py: Python<'_>, cached: Vec<Cached>) -> PyResult<()> {
+        let (later, now): (Vec<Cached>, Vec<Cached>) = cached
+            .into_iter()
+            .partition(|c| c.finalizer.as_ref().is_some_and(Finalizer::is_async));
+        if !later.is_empty() {
+            self.inner.write().unwrap().deferred.extend(later);
         }
-        None
+        teardown::run_all(py, now.into_iter().filter_map(|c| c.finalizer))
     }
 
-    fn set_cached(&self, py: Python<'_>, key: String, value: Py<PyAny>) -> PyResult<()> {
-        let mut g = self.inner.lock().unwrap();
-        for layer in g.overrides.iter_mut().rev() {
-            if let Some(p) = layer.get_mut(&key) {
-                if p.meta.singleton {
-                    p.cache = Some(clone_py(py, &value));
-                    return Ok(());
-                }
-            }
+    fn bump_generation(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+
+    fn compile_plan(&self, keys: &[String], allow_async: bool) -> PyResult<CompiledPlan> {
+        self.ensure_open()?;
+        let g = self.inner.read().unwrap();
+        // Read under the lock so the generation matches the tables we compile from
+        let generation = self.generation.load(Ordering::Acquire);
+        let mut plan = g.compile_plan(keys, allow_async, generation)?;
+        drop(g);
+        self.check_captive(&plan)?;
+        plan.wrap_errors = self.wrap_errors;
+        Ok(plan)
+    }
+
+    /// Apply the captive dependency mode to the singleton edges of `plan`.
+    fn check_captive(&self, plan: &CompiledPlan) -> PyResult<()> {
+        if self.captive == Captive::Ignore {
+            return Ok(());
         }
-        if let Some(p) = g.providers.get_mut(&key) {
-            if p.meta.singleton {
-                p.cache = Some(value);
-                return Ok(());
+        for (node, dep) in plan.captive_edges() {
+            self.captive_edge(&node.key, &dep.key, dep.provider.meta.lifetime.name())?;
+        }
+        Ok(())
+    }
+
+    /// Captive edges from the singleton `provider` to its dependencies, for
+    /// resolution paths that don't compile a plan: `(dependency, scope)`
+    /// pairs. `lookup` finds a dependency's key string and provider.
+    fn captive_deps<'a>(
+        &self,
+        provider: &Provider,
+        lookup: impl Fn(KeyId) -> Option<(&'a str, &'a Arc<Provider>)>,
+    ) -> Vec<(String, &'static str)> {
+        if self.captive == Captive::Ignore || !provider.is_singleton() {
+            return Vec::new();
+        }
+        provider
+            .meta
+            .deps
+            .iter()
+            .filter_map(|&d| lookup(d))
+            .filter(|(_, p)| !p.is_singleton())
+            .map(|(dep, p)| (dep.to_string(), p.meta.lifetime.name()))
+            .collect()
+    }
+
+    /// Raise or warn (once per edge) about singleton `key` depending on the
+    /// shorter-lived `dep`.
+    fn captive_edge(&self, key: &str, dep: &str, scope: &str) -> PyResult<()> {
+        if self.captive == Captive::Error {
+            return Err(errors::captive_dependency(key, dep, scope));
+        }
+        if self.warned.lock().unwrap().insert((key.to_string(), dep.to_string())) {
+            let message = errors::captive_message(key, dep, scope);
+            Python::attach(|py| {
+                let category = py.get_type::<errors::CaptiveDependencyWarning>();
+                PyErr::warn(py, &category, &CString::new(message)?, 1)
+            })?;
+        }
+        Ok(())
+    }
+
+    fn hook(&self, py: Python<'_>) -> Option<Py<PyAny>> {
+        let g = self.inner.read().unwrap();
+        g.hook.as_ref().map(|h| clone_py(py, h))
+    }
+
+    /// The scope entered in the current context, else `default`.
+    ///
+    /// Fails for closed scopes and scopes created by another container.
+    fn active_scope(
+        &self,
+        py: Python<'_>,
+        default: Option<Py<Scope>>,
+    ) -> PyResult<Option<Py<Scope>>> {
+        let current = self.scope_var.bind(py).call_method1(intern!(py, "get"), (py.None(),))?;
+        let scope = if current.is_none() {
+            default
+        } else {
+            Some(current.cast_into::<Scope>()?.unbind())
+        };
+        if let Some(s) = &scope {
+            if !s.get().belongs_to(self) {
+                return Err(errors::scope_error(None, "Scope belongs to a different container"));
+            }
+            if s.get().is_closed() {
+                return Err(errors::scope_error(None, "Cannot resolve in a closed scope"));
             }
         }
-        Err(PyRuntimeError::new_err(format!(
-            "Cannot set cache for non-singleton or unknown key: {}",
-            key
-        )))
+        Ok(scope)
     }
 
-    fn end_override_layer(&self) {
-        let mut g = self.inner.lock().unwrap();
-        g.pop_layer();
+    /// Start async execution of `compiled` in the caller's active scope.
+    fn run_async(
+        &self,
+        py: Python<'_>,
+        compiled: Arc<CompiledPlan>,
+        default_scope: Option<Py<Scope>>,
+        single: bool,
+    ) -> PyResult<Awaitable> {
+        self.ensure_open()?;
+        // Capture the scope now, in the caller's context, not when first awaited
+        let scope = if compiled.needs_scope { self.active_scope(py, default_scope)? } else { None };
+        let task = AsyncPlanRun::new(compiled, self.hook(py), scope, single);
+        Ok(Awaitable::new(Box::new(task)))
     }
 }
 
-#[pymodule]
-fn _fastdi_core(_py: Python, m: &pyo3::prelude::Bound<PyModule>) -> PyResult<()> {
+#[pymodule(gil_used = false)]
+fn _fastdi_core(m: &pyo3::prelude::Bound<PyModule>) -> PyResult<()> {
     m.add_class::<Container>()?;
+    errors::register(m)?;
+    m.add_class::<Plan>()?;
+    m.add_class::<Injector>()?;
+    m.add_class::<Scope>()?;
+    m.add_class::<Awaitable>()?;
+    m.add_class::<ValidationReport>()?;
+    m.add_class::<Problem>()?;
     Ok(())
 }
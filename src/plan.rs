@@ -0,0 +1,619 @@
+//! Compiled resolution plans and their sync/async executors.
+
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+use pyo3::prelude::*;
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::intern;
+use pyo3::sync::PyOnceLock;
+use pyo3::types::{PyCFunction, PyDict, PyList, PyTuple};
+
+use crate::aio::{AsyncTask, Awaitable, Inflight, Poll, Resume};
+use crate::args::Args;
+use crate::scope::Scope;
+use crate::flight::{Actor, FlightGuard, Waiting};
+use crate::teardown::Finalizer;
+use crate::{clone_py, errors, Claim, Container, Provider};
+
+pub(crate) struct PlanNode {
+    pub(crate) key: String,
+    pub(crate) provider: Arc<Provider>,
+    // Indices of dependency nodes, in provider argument order
+    pub(crate) deps: Vec<usize>,
+}
+
+/// Topologically ordered nodes for a set of roots, valid for one container
+/// generation.
+pub(crate) struct CompiledPlan {
+    pub(crate) generation: u64,
+    pub(crate) nodes: Vec<PlanNode>,
+    pub(crate) roots: Vec<usize>,
+    // Node indices grouped by dependency depth: every node's dependencies live
+    // in earlier levels, so nodes of one level are independent of each other.
+    pub(crate) levels: Vec<Vec<usize>>,
+    // Whether any node caches in or tears down through the active scope
+    pub(crate) needs_scope: bool,
+    // Raise provider failures as `ResolutionError` with the dependency path
+    pub(crate) wrap_errors: bool,
+}
+
+impl CompiledPlan {
+    /// Build a plan from topologically ordered `nodes`.
+    pub(crate) fn new(generation: u64, nodes: Vec<PlanNode>, roots: Vec<usize>) -> Self {
+        let mut depth: Vec<usize> = Vec::with_capacity(nodes.len());
+        let mut levels: Vec<Vec<usize>> = Vec::new();
+        for (i, node) in nodes.iter().enumerate() {
+            let d = node.deps.iter().map(|&dep| depth[dep] + 1).max().unwrap_or(0);
+            depth.push(d);
+            if levels.len() <= d {
+                levels.resize_with(d + 1, Vec::new);
+            }
+            levels[d].push(i);
+        }
+        let needs_scope = nodes.iter().any(|n| n.provider.needs_scope());
+        Self { generation, nodes, roots, levels, needs_scope, wrap_errors: false }
+    }
+
+    /// Edges from singleton nodes to shorter-lived dependencies.
+    pub(crate) fn captive_edges(&self) -> impl Iterator<Item = (&PlanNode, &PlanNode)> {
+        self.nodes.iter().filter(|n| n.provider.is_singleton()).flat_map(move |n| {
+            n.deps.iter().map(|&d| &self.nodes[d]).filter(|d| !d.provider.is_singleton()).map(
+                move |d| (n, d),
+            )
+        })
+    }
+
+    /// Keys leading from a root down to the dependent of node `target`
+    /// (empty when `target` is a root).
+    fn path_to(&self, target: usize) -> Vec<String> {
+        fn walk(
+            plan: &CompiledPlan,
+            at: usize,
+            target: usize,
+            seen: &mut [bool],
+            path: &mut Vec<usize>,
+        ) -> bool {
+            // A node whose dependencies were searched once can't lead to `target`
+            if at == target || std::mem::replace(&mut seen[at], true) {
+                return at == target;
+            }
+            path.push(at);
+            if plan.nodes[at].deps.iter().any(|&d| walk(plan, d, target, seen, path)) {
+                return true;
+            }
+            path.pop();
+            false
+        }
+        let mut seen = vec![false; self.nodes.len()];
+        let mut path = Vec::new();
+        for &root in &self.roots {
+            if walk(self, root, target, &mut seen, &mut path) {
+                break;
+            }
+        }
+        path.into_iter().map(|i| self.nodes[i].key.clone()).collect()
+    }
+
+    /// Wrap the failure of node `i`'s provider when `wrap_errors` is set.
+    fn failure(&self, py: Python<'_>, err: PyErr, i: usize) -> PyErr {
+        if !self.wrap_errors {
+            return err;
+        }
+        errors::resolution_error(py, err, &self.nodes[i].key, &self.path_to(i))
+    }
+
+    /// Deadline passed on by `node`'s dependencies to its cached value.
+    fn inherited(&self, node: &PlanNode) -> Option<f64> {
+        node.provider.inherited_from(node.deps.iter().map(|&d| &self.nodes[d].provider))
+    }
+
+    fn args(
+        &self,
+        py: Python<'_>,
+        node: &PlanNode,
+        values: &[Option<Py<PyAny>>],
+    ) -> PyResult<Args> {
+        node.deps
+            .iter()
+            .map(|&d| {
+                values[d].as_ref().map(|v| clone_py(py, v)).ok_or_else(|| {
+                    PyRuntimeError::new_err(format!(
+                        "Internal error: dependency {} not computed before {}",
+                        self.nodes[d].key, node.key
+                    ))
+                })
+            })
+            .collect()
+    }
+
+    fn outputs(&self, py: Python<'_>, values: &[Option<Py<PyAny>>]) -> PyResult<Vec<Py<PyAny>>> {
+        self.roots
+            .iter()
+            .map(|&r| {
+                values[r].as_ref().map(|v| clone_py(py, v)).ok_or_else(|| {
+                    PyRuntimeError::new_err(format!(
+                        "Internal error: key {} missing after plan execution",
+                        self.nodes[r].key
+                    ))
+                })
+            })
+            .collect()
+    }
+
+    /// Execute synchronously, computing each node once; returns root values.
+    pub(crate) fn run(&self, py: Python<'_>, scope: Option<&Scope>) -> PyResult<Vec<Py<PyAny>>> {
+        let mut values: Vec<Option<Py<PyAny>>> = Vec::with_capacity(self.nodes.len());
+        for (i, node) in self.nodes.iter().enumerate() {
+            let p = &node.provider;
+            let _flight = match p.claim_sync(py, &node.key, scope)? {
+                Claim::Cached(cached) => {
+                    values.push(Some(cached));
+                    continue;
+                }
+                Claim::Build(guard) => guard,
+                Claim::Wait(_) => unreachable!("claim_sync waits for other initializations"),
+            };
+            if p.meta.is_async {
+                return Err(errors::async_in_sync(&node.key, &[]));
+            }
+            let args = self.args(py, node, &values)?;
+            let inherited = self.inherited(node);
+            let value = p
+                .call(py, &node.key, scope, args, inherited)
+                .map_err(|e| self.failure(py, e, i))?;
+            values.push(Some(value));
+        }
+        self.outputs(py, &values)
+    }
+}
+
+/// A compiled resolution plan owned by the Rust core.
+///
+/// Created by `Container.compile(keys)`. Holds the topological order and
+/// per-node dependency indices; `execute()` / `execute_async()` run it without
+/// recompiling until the container's registrations or overrides change.
+#[pyclass(frozen, module = "_fastdi_core")]
+pub(crate) struct Plan {
+    container: Py<Container>,
+    keys: Vec<String>,
+    allow_async: bool,
+    compiled: RwLock<Arc<CompiledPlan>>,
+}
+
+impl Plan {
+    pub(crate) fn new(
+        container: Py<Container>,
+        keys: Vec<String>,
+        allow_async: bool,
+        compiled: CompiledPlan,
+    ) -> Self {
+        Self { container, keys, allow_async, compiled: RwLock::new(Arc::new(compiled)) }
+    }
+
+    /// Current compiled plan, recompiling if the container generation moved.
+    fn current(&self) -> PyResult<Arc<CompiledPlan>> {
+        let container = self.container.get();
+        let generation = container.generation.load(Ordering::Acquire);
+        {
+            let compiled = self.compiled.read().unwrap();
+            if compiled.generation == generation {
+                return Ok(compiled.clone());
+            }
+        }
+        let fresh = Arc::new(container.compile_plan(&self.keys, self.allow_async)?);
+        *self.compiled.write().unwrap() = fresh.clone();
+        Ok(fresh)
+    }
+}
+
+#[pymethods]
+impl Plan {
+    /// Root keys in the order values are returned.
+    #[getter]
+    fn keys(&self) -> Vec<String> {
+        self.keys.clone()
+    }
+
+    /// Topologically sorted keys; dependencies appear before dependents.
+    #[getter]
+    fn order(&self) -> PyResult<Vec<String>> {
+        Ok(self.current()?.nodes.iter().map(|n| n.key.clone()).collect())
+    }
+
+    /// Whether executing the plan uses the active request scope (request-scoped
+    /// providers, or transient providers with teardown).
+    #[getter]
+    fn needs_scope(&self) -> PyResult<bool> {
+        Ok(self.current()?.needs_scope)
+    }
+
+    /// Execute synchronously and return the root values.
+    pub(crate) fn execute(&self, py: Python<'_>) -> PyResult<Vec<Py<PyAny>>> {
+        let container = self.container.get();
+        container.ensure_open()?;
+        let compiled = self.current()?;
+        let scope = if compiled.needs_scope { container.active_scope(py, None)? } else { None };
+        compiled.run(py, scope.as_ref().map(|s| s.get()))
+    }
+
+    /// Execute asynchronously; returns an awaitable producing the root values.
+    ///
+    /// `default_scope` is used for request-scoped providers when no scope is
+    /// active in the current context.
+    #[pyo3(signature = (default_scope=None))]
+    fn execute_async(
+        &self,
+        py: Python<'_>,
+        default_scope: Option<Py<Scope>>,
+    ) -> PyResult<Awaitable> {
+        let compiled = self.current()?;
+        self.container.get().run_async(py, compiled, default_scope, false)
+    }
+}
+
+pub(crate) fn emit(
+    py: Python<'_>,
+    hook: &Option<Py<PyAny>>,
+    event: &str,
+    fill: impl FnOnce(&Bound<'_, PyDict>) -> PyResult<()>,
+) {
+    if let Some(h) = hook {
+        let payload = PyDict::new(py);
+        if fill(&payload).is_ok() {
+            // Hooks must never break resolution
+            let _ = h.bind(py).call1((event, payload));
+        }
+    }
+}
+
+/// Async plan executor driven by the event loop through [`Awaitable`].
+///
+/// Runs the compiled plan one dependency level at a time, calling providers
+/// without holding the container lock. Sync providers and cache hits of a
+/// level are computed first; the level's coroutine providers are then awaited
+/// in place when there is one, or concurrently through `asyncio.gather` when
+/// there are several. Singletons being initialized by another caller are
+/// awaited alongside and looked up again once they land. A gathered level
+/// always runs to completion: when one provider fails, the values its
+/// siblings built are still stored (so their teardown has an owner and their
+/// initialization lands) before the first failure is raised.
+pub(crate) struct AsyncPlanRun {
+    plan: Arc<CompiledPlan>,
+    hook: Option<Py<PyAny>>,
+    scope: Option<Py<Scope>>,
+    // Produce the single root value instead of a list
+    single: bool,
+    values: Vec<Option<Py<PyAny>>>,
+    // Level being computed; it is done once all its nodes have values
+    level: usize,
+    pending: Option<Pending>,
+}
+
+/// Awaitables of one level currently being awaited.
+struct Pending {
+    // In the order the awaitables were created
+    nodes: Vec<Awaited>,
+    // Awaits a single awaitable, or the `gather` future for several
+    inflight: Inflight,
+    // Tasks of the gathered awaitables, in `nodes` order
+    tasks: Vec<Py<PyAny>>,
+    // Registrations of the running task as waiting for the awaited
+    // initializations and gathered tasks, held until the level is done
+    _waits: Vec<Waiting>,
+}
+
+/// A node of the level being awaited.
+struct Awaited {
+    index: usize,
+    started: Instant,
+    // Finalizer of an async generator or async context manager being entered
+    finalizer: Option<Finalizer>,
+    // Held while this run initializes a singleton; shared with the callback
+    // landing it early if a gathered task fails
+    flight: Option<Arc<FlightGuard>>,
+    // Awaits another caller's initialization of the singleton rather than
+    // the provider
+    waiting: bool,
+}
+
+impl Drop for Awaited {
+    fn drop(&mut self) {
+        // Land the initialization for waiters, whether or not it was stored
+        if let Some(flight) = &self.flight {
+            Python::attach(|py| flight.release(py));
+        }
+    }
+}
+
+static GATHER: PyOnceLock<Py<PyAny>> = PyOnceLock::new();
+static ENSURE_FUTURE: PyOnceLock<Py<PyAny>> = PyOnceLock::new();
+
+impl AsyncPlanRun {
+    pub(crate) fn new(
+        plan: Arc<CompiledPlan>,
+        hook: Option<Py<PyAny>>,
+        scope: Option<Py<Scope>>,
+        single: bool,
+    ) -> Self {
+        let values = (0..plan.nodes.len()).map(|_| None).collect();
+        Self { plan, hook, scope, single, values, level: 0, pending: None }
+    }
+
+    /// Compute the unresolved nodes of the current level; its async providers
+    /// and waits for other initializations are left in `pending`.
+    fn start_level(&mut self, py: Python<'_>) -> PyResult<()> {
+        let plan = self.plan.clone();
+        let level = &plan.levels[self.level];
+
+        let mut nodes: Vec<Awaited> = Vec::new();
+        let mut coros: Vec<Bound<'_, PyAny>> = Vec::new();
+        let mut calls: Vec<(usize, Option<FlightGuard>)> = Vec::new();
+        let mut waits = Vec::new();
+        for &i in level {
+            if self.values[i].is_some() {
+                continue;
+            }
+            let node = &plan.nodes[i];
+            let p = &node.provider;
+            let scope = self.scope.as_ref().map(|s| s.get());
+            let flight = match p.claim(py, scope, p.meta.is_async) {
+                Claim::Cached(cached) => {
+                    emit(py, &self.hook, "cache_hit", |d| {
+                        d.set_item("key", &node.key)?;
+                        d.set_item("scope", p.meta.lifetime.name())
+                    });
+                    self.values[i] = Some(cached);
+                    continue;
+                }
+                Claim::Wait(flight) => {
+                    // If it landed already, the node is claimed again right away
+                    if let Some((future, waiting)) = flight.subscribe(py, &node.key)? {
+                        waits.push(waiting);
+                        let started = Instant::now();
+                        nodes.push(Awaited {
+                            index: i,
+                            started,
+                            finalizer: None,
+                            flight: None,
+                            waiting: true,
+                        });
+                        coros.push(future);
+                    }
+                    continue;
+                }
+                Claim::Build(flight) => flight,
+            };
+            if p.meta.is_async {
+                calls.push((i, flight));
+                continue;
+            }
+            let started = self.provider_start(py, node);
+            let args = plan.args(py, node, &self.values)?;
+            let inherited = plan.inherited(node);
+            let value = p
+                .call(py, &node.key, scope, args, inherited)
+                .map_err(|e| plan.failure(py, e, i))?;
+            self.finish(py, i, started, value);
+        }
+
+        for (i, flight) in calls {
+            let node = &plan.nodes[i];
+            let started = self.provider_start(py, node);
+            let scope = self.scope.as_ref().map(|s| s.get());
+            let call = node
+                .provider
+                .check_owner(&node.key, scope)
+                .and_then(|_| plan.args(py, node, &self.values))
+                .and_then(|args| args.call(node.provider.callable.bind(py)))
+                .and_then(|produced| node.provider.meta.kind.start_async(py, produced));
+            match call {
+                Ok((coro, finalizer)) => {
+                    let flight = flight.map(Arc::new);
+                    nodes.push(Awaited { index: i, started, finalizer, flight, waiting: false });
+                    coros.push(coro);
+                }
+                Err(err) => {
+                    // Don't leave already created coroutines un-awaited
+                    for coro in &coros {
+                        let _ = coro.call_method0("close");
+                    }
+                    return Err(plan.failure(py, err, i));
+                }
+            }
+        }
+        let mut tasks = Vec::new();
+        let inflight = match coros.len() {
+            0 => return Ok(()),
+            1 => Inflight::start(&coros[0])?,
+            _ => {
+                // Keep the tasks `gather` would create, to read each outcome
+                let ensure_future = ENSURE_FUTURE.import(py, "asyncio", "ensure_future")?;
+                let parent = Actor::current(py, true);
+                for (coro, awaited) in coros.iter_mut().zip(&nodes) {
+                    *coro = ensure_future.call1((&*coro,))?;
+                    tasks.push(coro.clone().unbind());
+                    // The task builds the singleton while this one awaits it
+                    let task = Actor::task(coro);
+                    if let Some(flight) = &awaited.flight {
+                        flight.hand_over(task);
+                        // Waiters, possibly siblings, retry as soon as it fails
+                        let flight = flight.clone();
+                        let release = PyCFunction::new_closure(py, None, None, move |args, _| {
+                            let py = args.py();
+                            let task = args.get_item(0)?;
+                            let failed = task.call_method0(intern!(py, "cancelled"))?.is_truthy()?
+                                || !task.call_method0(intern!(py, "exception"))?.is_none();
+                            if failed {
+                                flight.release(py);
+                            }
+                            PyResult::Ok(())
+                        })?;
+                        coro.call_method1(intern!(py, "add_done_callback"), (release,))?;
+                    }
+                    waits.push(Waiting::on_task(parent, task));
+                }
+                // Siblings of a failing provider keep running to completion
+                let kwargs = PyDict::new(py);
+                kwargs.set_item(intern!(py, "return_exceptions"), true)?;
+                let gather = GATHER.import(py, "asyncio", "gather")?;
+                Inflight::start(&gather.call(PyTuple::new(py, coros)?, Some(&kwargs))?)?
+            }
+        };
+        self.pending = Some(Pending { nodes, inflight, tasks, _waits: waits });
+        Ok(())
+    }
+
+    /// Attribute a failure of a single awaited node to it; a gathered level
+    /// only fails when it is cancelled.
+    fn awaited_failure(&self, py: Python<'_>, err: PyErr) -> PyErr {
+        match self.pending.as_ref().map(|p| &p.nodes[..]) {
+            Some([awaited]) if !awaited.waiting => self.plan.failure(py, err, awaited.index),
+            _ => err,
+        }
+    }
+
+    fn provider_start(&self, py: Python<'_>, node: &PlanNode) -> Instant {
+        emit(py, &self.hook, "provider_start", |d| {
+            d.set_item("key", &node.key)?;
+            d.set_item("async", node.provider.meta.is_async)
+        });
+        Instant::now()
+    }
+
+    fn finish(&mut self, py: Python<'_>, i: usize, started: Instant, value: Py<PyAny>) {
+        let node = &self.plan.nodes[i];
+        emit(py, &self.hook, "provider_end", |d| {
+            d.set_item("key", &node.key)?;
+            d.set_item("async", node.provider.meta.is_async)?;
+            d.set_item("duration_s", started.elapsed().as_secs_f64())
+        });
+        self.values[i] = Some(value);
+    }
+
+    /// Record the result of an awaited async provider. Nodes that waited for
+    /// another initialization stay unresolved and are claimed again.
+    fn finish_async(
+        &mut self,
+        py: Python<'_>,
+        mut awaited: Awaited,
+        result: Bound<'_, PyAny>,
+    ) -> PyResult<()> {
+        if awaited.waiting {
+            return Ok(());
+        }
+        let node = &self.plan.nodes[awaited.index];
+        let p = &node.provider;
+        let scope = self.scope.as_ref().map(|s| s.get());
+        let inherited = self.plan.inherited(node);
+        let value = if p.meta.kind.is_async() {
+            p.keep(py, scope, result.unbind(), awaited.finalizer.take(), inherited)
+        } else {
+            p.remember(py, &node.key, scope, result, inherited)
+        };
+        let value = value.map_err(|e| self.plan.failure(py, e, awaited.index))?;
+        self.finish(py, awaited.index, awaited.started, value);
+        // Land the initialization for waiters now that the value is stored
+        drop(awaited);
+        Ok(())
+    }
+
+    /// Record the results of the awaited level; with several nodes, every
+    /// result is stored before the first failure is raised.
+    fn finish_pending(
+        &mut self,
+        py: Python<'_>,
+        pending: Pending,
+        value: Py<PyAny>,
+    ) -> PyResult<()> {
+        let mut nodes = pending.nodes;
+        if nodes.len() == 1 {
+            return self.finish_async(py, nodes.remove(0), value.into_bound(py));
+        }
+        let mut failure = None;
+        for (awaited, task) in nodes.into_iter().zip(&pending.tasks) {
+            let index = awaited.index;
+            let outcome = match task.bind(py).call_method0(intern!(py, "result")) {
+                Ok(result) => self.finish_async(py, awaited, result),
+                // Dropping `awaited` releases the flight, so waiters retry
+                Err(err) if awaited.waiting => Err(err),
+                Err(err) => Err(self.plan.failure(py, err, index)),
+            };
+            if let Err(err) = outcome {
+                failure.get_or_insert(err);
+            }
+        }
+        failure.map_or(Ok(()), Err)
+    }
+
+    /// Drop the awaited level after it was cancelled or closed. Values that
+    /// gathered tasks finished building are stored, so their teardown keeps
+    /// an owner; unfinished tasks are cancelled.
+    fn abandon(&mut self, py: Python<'_>) {
+        let Some(pending) = self.pending.take() else { return };
+        for (awaited, task) in pending.nodes.into_iter().zip(&pending.tasks) {
+            let task = task.bind(py);
+            let done = task.call_method0(intern!(py, "done")).and_then(|d| d.is_truthy());
+            if !done.unwrap_or(false) {
+                let _ = task.call_method0(intern!(py, "cancel"));
+                continue;
+            }
+            if let Ok(result) = task.call_method0(intern!(py, "result")) {
+                if let Err(err) = self.finish_async(py, awaited, result) {
+                    err.write_unraisable(py, None);
+                }
+            }
+        }
+    }
+
+    fn output(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        let mut out = self.plan.outputs(py, &self.values)?;
+        if self.single {
+            return Ok(out.pop().unwrap_or_else(|| py.None()));
+        }
+        Ok(PyList::new(py, out)?.into_any().unbind())
+    }
+}
+
+impl AsyncTask for AsyncPlanRun {
+    fn resume(&mut self, py: Python<'_>, mut input: Resume) -> PyResult<Poll> {
+        loop {
+            if let Some(pending) = &self.pending {
+                let polled = match pending.inflight.resume(py, input) {
+                    Ok(polled) => polled,
+                    Err(err) => {
+                        let err = self.awaited_failure(py, err);
+                        self.abandon(py);
+                        return Err(err);
+                    }
+                };
+                match polled {
+                    Poll::Pending(yielded) => return Ok(Poll::Pending(yielded)),
+                    Poll::Ready(value) => {
+                        let pending = self.pending.take().unwrap();
+                        self.finish_pending(py, pending, value)?;
+                    }
+                }
+                input = Resume::Send(py.None());
+            } else if let Resume::Throw(err) = input {
+                return Err(err);
+            }
+            while self.level < self.plan.levels.len()
+                && self.plan.levels[self.level].iter().all(|&i| self.values[i].is_some())
+            {
+                self.level += 1;
+            }
+            if self.level == self.plan.levels.len() {
+                return Ok(Poll::Ready(self.output(py)?));
+            }
+            self.start_level(py)?;
+        }
+    }
+
+    fn close(&mut self, py: Python<'_>) {
+        if let Some(pending) = &self.pending {
+            pending.inflight.close(py);
+        }
+        self.abandon(py);
+    }
+}
@@ -0,0 +1,164 @@
+//! Request scope handles.
+//!
+//! A `Scope` owns the cached values of request-scoped providers. Entering it
+//! (`with scope:`) makes it the active scope of its container in the current
+//! context (thread or asyncio task) through a `contextvars.ContextVar`, so the
+//! sync and async resolvers pick it up without threading it through calls.
+//! The scope also owns the teardown of generator and context-manager providers
+//! resolved in it, which runs when it closes; `async with scope:` (or
+//! `aclose()`) awaits async teardown.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use pyo3::prelude::*;
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::intern;
+
+use crate::aio::{Awaitable, Ready};
+use crate::intern::KeyId;
+use crate::teardown::{self, AsyncTeardown, Finalizer};
+use crate::{errors, Container};
+
+#[pyclass(frozen, module = "_fastdi_core")]
+pub(crate) struct Scope {
+    container: Py<Container>,
+    cache: Mutex<HashMap<KeyId, Py<PyAny>>>,
+    // Teardown of values produced in this scope, in creation order
+    finalizers: Mutex<Vec<Finalizer>>,
+    // ContextVar tokens of nested `__enter__` calls, innermost last
+    tokens: Mutex<Vec<Py<PyAny>>>,
+    closed: AtomicBool,
+}
+
+impl Scope {
+    pub(crate) fn new(container: Py<Container>) -> Self {
+        Self {
+            container,
+            cache: Mutex::new(HashMap::new()),
+            finalizers: Mutex::new(Vec::new()),
+            tokens: Mutex::new(Vec::new()),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn belongs_to(&self, container: &Container) -> bool {
+        std::ptr::eq(self.container.get(), container)
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn get(&self, py: Python<'_>, id: KeyId) -> Option<Py<PyAny>> {
+        self.cache.lock().unwrap().get(&id).map(|v| v.clone_ref(py))
+    }
+
+    pub(crate) fn insert(&self, id: KeyId, value: Py<PyAny>) {
+        let previous = self.cache.lock().unwrap().insert(id, value);
+        drop(previous);
+    }
+
+    pub(crate) fn push_finalizer(&self, finalizer: Finalizer) {
+        self.finalizers.lock().unwrap().push(finalizer);
+    }
+
+    fn enter(&self, slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        if self.is_closed() {
+            return Err(errors::scope_error(None, "Cannot enter a closed scope"));
+        }
+        let var = self.container.get().scope_var.bind(py);
+        let token = var.call_method1(intern!(py, "set"), (slf,))?;
+        self.tokens.lock().unwrap().push(token.unbind());
+        Ok(())
+    }
+
+    /// Reset the innermost `enter`; returns whether it was the outermost one.
+    fn exit(&self, py: Python<'_>) -> PyResult<bool> {
+        let token = self.tokens.lock().unwrap().pop();
+        if let Some(token) = token {
+            let var = self.container.get().scope_var.bind(py);
+            var.call_method1(intern!(py, "reset"), (token,))?;
+        }
+        // Nested enters of the same scope close it only on the outermost exit
+        Ok(self.tokens.lock().unwrap().is_empty())
+    }
+
+    /// Mark the scope closed and drop its cache; returns its finalizers in
+    /// teardown (reverse creation) order.
+    fn shut(&self) -> Vec<Finalizer> {
+        self.closed.store(true, Ordering::Release);
+        let values = std::mem::take(&mut *self.cache.lock().unwrap());
+        drop(values);
+        let mut finalizers = std::mem::take(&mut *self.finalizers.lock().unwrap());
+        finalizers.reverse();
+        finalizers
+    }
+}
+
+#[pymethods]
+impl Scope {
+    /// Whether the scope has been closed; closed scopes reject resolution.
+    #[getter]
+    fn closed(&self) -> bool {
+        self.is_closed()
+    }
+
+    /// Whether some value's teardown must be awaited (`aclose()`).
+    #[getter]
+    fn has_async_teardown(&self) -> bool {
+        self.finalizers.lock().unwrap().iter().any(Finalizer::is_async)
+    }
+
+    /// Mark the scope closed, run the teardown of its values in reverse
+    /// creation order and drop its cache.
+    ///
+    /// Every finalizer runs even if some fail; the failures are raised
+    /// together as a `DisposeError`.
+    /// Fails without closing if some teardown must be awaited.
+    fn close(&self, py: Python<'_>) -> PyResult<()> {
+        if self.has_async_teardown() {
+            return Err(PyRuntimeError::new_err(
+                "Scope has async teardown; use 'async with' or aclose()",
+            ));
+        }
+        teardown::run_all(py, self.shut())
+    }
+
+    /// Async counterpart of `close()`; returns an awaitable that also awaits
+    /// the teardown of async generators and async context managers.
+    fn aclose(&self) -> Awaitable {
+        Awaitable::new(Box::new(AsyncTeardown::new(self.shut())))
+    }
+
+    fn __enter__(slf: Bound<'_, Self>) -> PyResult<Bound<'_, Self>> {
+        slf.get().enter(&slf)?;
+        Ok(slf)
+    }
+
+    #[pyo3(signature = (*_exc))]
+    fn __exit__(&self, py: Python<'_>, _exc: &Bound<'_, pyo3::types::PyTuple>) -> PyResult<bool> {
+        if self.exit(py)? {
+            self.close(py)?;
+        }
+        Ok(false)
+    }
+
+    fn __aenter__(slf: Bound<'_, Self>) -> PyResult<Awaitable> {
+        slf.get().enter(&slf)?;
+        Ok(Awaitable::new(Box::new(Ready::new(slf.into_any().unbind()))))
+    }
+
+    #[pyo3(signature = (*_exc))]
+    fn __aexit__(
+        &self,
+        py: Python<'_>,
+        _exc: &Bound<'_, pyo3::types::PyTuple>,
+    ) -> PyResult<Awaitable> {
+        if self.exit(py)? {
+            return Ok(self.aclose());
+        }
+        Ok(Awaitable::new(Box::new(Ready::new(py.None()))))
+    }
+}
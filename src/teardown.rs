@@ -0,0 +1,293 @@
+//! Generator and context-manager providers.
+//!
+//! A provider's [`Kind`] says how its return value becomes the injected value:
+//! a generator is advanced to its first `yield`, a context manager is entered.
+//! The resulting [`Finalizer`] is kept by the owner of the value (the provider
+//! entry for singletons, the active `Scope` otherwise) and run when that owner
+//! closes, in reverse creation order. Async generators and async context
+//! managers are entered by the async executor and torn down by
+//! [`AsyncTeardown`]. Registered disposers and the container's `close()` /
+//! `aclose()` fallback are finalizers as well.
+
+use pyo3::prelude::*;
+use pyo3::exceptions::{PyRuntimeError, PyStopAsyncIteration, PyStopIteration, PyValueError};
+use pyo3::intern;
+
+use crate::aio::{AsyncTask, Inflight, Poll, Resume};
+use crate::errors;
+
+/// How a provider's return value is turned into the injected value.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Kind {
+    // The return value is injected as-is
+    Value,
+    // Generator function: the first yielded value is injected
+    Generator,
+    // The return value is a context manager; `__enter__`'s result is injected
+    ContextManager,
+    // Async generator function: the first yielded value is injected
+    AsyncGenerator,
+    // The return value is an async context manager; `__aenter__`'s result is injected
+    AsyncContextManager,
+}
+
+impl Kind {
+    pub(crate) fn parse(kind: Option<&str>) -> PyResult<Self> {
+        match kind {
+            None | Some("value") => Ok(Kind::Value),
+            Some("generator") => Ok(Kind::Generator),
+            Some("context_manager") => Ok(Kind::ContextManager),
+            Some("async_generator") => Ok(Kind::AsyncGenerator),
+            Some("async_context_manager") => Ok(Kind::AsyncContextManager),
+            Some(other) => Err(PyValueError::new_err(format!(
+                "Unknown provider kind '{}'; expected 'value', 'generator', 'context_manager', \
+                 'async_generator' or 'async_context_manager'",
+                other
+            ))),
+        }
+    }
+
+    /// Whether values of this kind need teardown.
+    pub(crate) fn has_teardown(self) -> bool {
+        self != Kind::Value
+    }
+
+    /// Whether entering values of this kind must be awaited.
+    pub(crate) fn is_async(self) -> bool {
+        matches!(self, Kind::AsyncGenerator | Kind::AsyncContextManager)
+    }
+
+    /// Produce the injected value from a provider's return value.
+    pub(crate) fn enter(
+        self,
+        py: Python<'_>,
+        key: &str,
+        produced: Bound<'_, PyAny>,
+    ) -> PyResult<(Py<PyAny>, Option<Finalizer>)> {
+        match self {
+            Kind::Value => Ok((produced.unbind(), None)),
+            Kind::Generator => match produced.call_method0(intern!(py, "__next__")) {
+                Ok(value) => Ok((value.unbind(), Some(Finalizer::Generator(produced.unbind())))),
+                Err(err) if err.is_instance_of::<PyStopIteration>(py) => {
+                    Err(PyRuntimeError::new_err(format!(
+                        "Generator provider for key '{}' did not yield a value",
+                        key
+                    )))
+                }
+                Err(err) => Err(err),
+            },
+            Kind::ContextManager => {
+                let value = produced.call_method0(intern!(py, "__enter__"))?;
+                Ok((value.unbind(), Some(Finalizer::ContextManager(produced.unbind()))))
+            }
+            Kind::AsyncGenerator | Kind::AsyncContextManager => Err(errors::async_enter(key)),
+        }
+    }
+
+    /// Start entering an async provider's return value.
+    ///
+    /// Returns the awaitable producing the injected value and, for async
+    /// generators and async context managers, the value's finalizer. Other
+    /// kinds pass `produced` (a coroutine) through and are entered once it has
+    /// been awaited.
+    pub(crate) fn start_async<'py>(
+        self,
+        py: Python<'py>,
+        produced: Bound<'py, PyAny>,
+    ) -> PyResult<(Bound<'py, PyAny>, Option<Finalizer>)> {
+        match self {
+            Kind::AsyncGenerator => {
+                let first = produced.call_method0(intern!(py, "__anext__"))?;
+                Ok((first, Some(Finalizer::AsyncGenerator(produced.unbind()))))
+            }
+            Kind::AsyncContextManager => {
+                let entered = produced.call_method0(intern!(py, "__aenter__"))?;
+                Ok((entered, Some(Finalizer::AsyncContextManager(produced.unbind()))))
+            }
+            _ => Ok((produced, None)),
+        }
+    }
+}
+
+/// Teardown step of a produced value.
+pub(crate) enum Finalizer {
+    Generator(Py<PyAny>),
+    ContextManager(Py<PyAny>),
+    AsyncGenerator(Py<PyAny>),
+    AsyncContextManager(Py<PyAny>),
+    // Registered disposer called with the value; may return an awaitable
+    Dispose { disposer: Py<PyAny>, value: Py<PyAny> },
+    // Container shutdown fallback: the value's `close()`, or `aclose()` when
+    // awaiting
+    Close(Py<PyAny>),
+}
+
+impl Finalizer {
+    /// Whether this teardown must be awaited.
+    pub(crate) fn is_async(&self) -> bool {
+        matches!(self, Finalizer::AsyncGenerator(_) | Finalizer::AsyncContextManager(_))
+    }
+
+    /// Run a sync teardown; async ones are rejected.
+    pub(crate) fn run(self, py: Python<'_>) -> PyResult<()> {
+        if self.is_async() {
+            return Err(PyRuntimeError::new_err("Async teardown must be awaited with aclose()"));
+        }
+        self.start(py, false).map(|_| ())
+    }
+
+    /// Run a sync teardown to completion, or start an async one.
+    ///
+    /// Without `awaiting`, an awaitable returned by a disposer or `close()` is
+    /// closed and reported as an error.
+    fn start(self, py: Python<'_>, awaiting: bool) -> PyResult<Option<AsyncStep>> {
+        match self {
+            Finalizer::Generator(gen) => {
+                let gen = gen.bind(py);
+                match gen.call_method0(intern!(py, "__next__")) {
+                    Ok(_) => {
+                        gen.call_method0(intern!(py, "close"))?;
+                        Err(PyRuntimeError::new_err("Generator provider yielded more than once"))
+                    }
+                    Err(err) if err.is_instance_of::<PyStopIteration>(py) => Ok(None),
+                    Err(err) => Err(err),
+                }
+            }
+            Finalizer::ContextManager(cm) => {
+                let none = || py.None();
+                cm.bind(py).call_method1(intern!(py, "__exit__"), (none(), none(), none()))?;
+                Ok(None)
+            }
+            Finalizer::AsyncGenerator(gen) => {
+                let next = gen.bind(py).call_method0(intern!(py, "__anext__"))?;
+                Ok(Some(AsyncStep { inflight: Inflight::start(&next)?, generator: Some(gen) }))
+            }
+            Finalizer::AsyncContextManager(cm) => {
+                let none = || py.None();
+                let exit = cm
+                    .bind(py)
+                    .call_method1(intern!(py, "__aexit__"), (none(), none(), none()))?;
+                Ok(Some(AsyncStep { inflight: Inflight::start(&exit)?, generator: None }))
+            }
+            Finalizer::Dispose { disposer, value } => {
+                settle(disposer.bind(py).call1((value,))?, awaiting)
+            }
+            Finalizer::Close(value) => {
+                let value = value.bind(py);
+                if awaiting && value.hasattr(intern!(py, "aclose"))? {
+                    return settle(value.call_method0(intern!(py, "aclose"))?, true);
+                }
+                if value.hasattr(intern!(py, "close"))? {
+                    return settle(value.call_method0(intern!(py, "close"))?, awaiting);
+                }
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Await `result` if it is awaitable and awaiting is possible.
+fn settle(result: Bound<'_, PyAny>, awaiting: bool) -> PyResult<Option<AsyncStep>> {
+    let py = result.py();
+    if !result.hasattr(intern!(py, "__await__"))? {
+        return Ok(None);
+    }
+    if awaiting {
+        return Ok(Some(AsyncStep { inflight: Inflight::start(&result)?, generator: None }));
+    }
+    if result.hasattr(intern!(py, "close"))? {
+        result.call_method0(intern!(py, "close"))?;
+    }
+    Err(PyRuntimeError::new_err("Teardown returned an awaitable; use aclose()"))
+}
+
+/// Run `finalizers` in the given order; every one runs even if an earlier one
+/// fails, and the failures are combined by [`errors::aggregate`].
+pub(crate) fn run_all(
+    py: Python<'_>,
+    finalizers: impl IntoIterator<Item = Finalizer>,
+) -> PyResult<()> {
+    let failures = finalizers.into_iter().filter_map(|f| f.run(py).err()).collect();
+    errors::aggregate(py, failures)
+}
+
+/// An async teardown being awaited.
+struct AsyncStep {
+    inflight: Inflight,
+    // The async generator being finished; it must stop instead of yielding
+    generator: Option<Py<PyAny>>,
+}
+
+/// Runs finalizers in order, awaiting async ones, with the semantics of
+/// [`run_all`].
+pub(crate) struct AsyncTeardown {
+    finalizers: std::vec::IntoIter<Finalizer>,
+    current: Option<AsyncStep>,
+    errors: Vec<PyErr>,
+}
+
+impl AsyncTeardown {
+    pub(crate) fn new(finalizers: Vec<Finalizer>) -> Self {
+        Self { finalizers: finalizers.into_iter(), current: None, errors: Vec::new() }
+    }
+
+    fn fail(&mut self, err: PyErr) {
+        self.errors.push(err);
+    }
+
+    /// Record the outcome of the awaited step; may start `aclose()` on an
+    /// async generator that yielded again.
+    fn step_done(&mut self, py: Python<'_>, step: AsyncStep, outcome: PyResult<()>) {
+        match (outcome, step.generator) {
+            (Ok(()), Some(gen)) => {
+                let msg = "Async generator provider yielded more than once";
+                self.fail(PyRuntimeError::new_err(msg));
+                let closing = gen
+                    .bind(py)
+                    .call_method0(intern!(py, "aclose"))
+                    .and_then(|aw| Inflight::start(&aw));
+                match closing {
+                    Ok(inflight) => self.current = Some(AsyncStep { inflight, generator: None }),
+                    Err(err) => self.fail(err),
+                }
+            }
+            (Err(err), Some(_)) if err.is_instance_of::<PyStopAsyncIteration>(py) => {}
+            (Err(err), _) => self.fail(err),
+            (Ok(()), None) => {}
+        }
+    }
+}
+
+impl AsyncTask for AsyncTeardown {
+    fn resume(&mut self, py: Python<'_>, mut input: Resume) -> PyResult<Poll> {
+        loop {
+            if let Some(step) = &self.current {
+                let outcome = match step.inflight.resume(py, input) {
+                    Ok(Poll::Pending(yielded)) => return Ok(Poll::Pending(yielded)),
+                    Ok(Poll::Ready(_)) => Ok(()),
+                    Err(err) => Err(err),
+                };
+                let step = self.current.take().unwrap();
+                self.step_done(py, step, outcome);
+                input = Resume::Send(py.None());
+                continue;
+            } else if let Resume::Throw(err) = input {
+                return Err(err);
+            }
+            let Some(finalizer) = self.finalizers.next() else {
+                errors::aggregate(py, std::mem::take(&mut self.errors))?;
+                return Ok(Poll::Ready(py.None()));
+            };
+            match finalizer.start(py, true) {
+                Ok(step) => self.current = step,
+                Err(err) => self.fail(err),
+            }
+        }
+    }
+
+    fn close(&mut self, py: Python<'_>) {
+        if let Some(step) = self.current.take() {
+            step.inflight.close(py);
+        }
+    }
+}
@@ -0,0 +1,94 @@
+//! Time-to-live of cached singletons.
+//!
+//! A singleton registered with a TTL is rebuilt on the first resolution after
+//! its deadline. Deadlines can cascade: a singleton built from a value whose
+//! provider expires its dependents inherits that value's deadline.
+
+use std::sync::OnceLock;
+use std::time::Instant;
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+
+/// Source of the current time, in seconds.
+pub(crate) enum Clock {
+    /// Seconds since the first reading, from a monotonic clock
+    Monotonic,
+    /// A Python callable returning seconds as a float, e.g. a test's fake clock
+    Custom(Py<PyAny>),
+}
+
+static EPOCH: OnceLock<Instant> = OnceLock::new();
+
+impl Clock {
+    pub(crate) fn new(clock: Option<Py<PyAny>>) -> Self {
+        clock.map_or(Clock::Monotonic, Clock::Custom)
+    }
+
+    /// Current time. A failing custom clock is reported as unraisable and
+    /// reads as the monotonic time, so cache lookups never fail.
+    pub(crate) fn now(&self, py: Python<'_>) -> f64 {
+        let monotonic = || EPOCH.get_or_init(Instant::now).elapsed().as_secs_f64();
+        match self {
+            Clock::Monotonic => monotonic(),
+            Clock::Custom(clock) => match clock.bind(py).call0().and_then(|t| t.extract()) {
+                Ok(now) => now,
+                Err(err) => {
+                    err.write_unraisable(py, Some(clock.bind(py)));
+                    monotonic()
+                }
+            },
+        }
+    }
+}
+
+/// A provider's time-to-live.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Ttl {
+    pub(crate) seconds: f64,
+    // Singletons built from this provider's value expire with it
+    pub(crate) expire_dependents: bool,
+}
+
+impl Ttl {
+    pub(crate) fn parse(seconds: Option<f64>, expire_dependents: bool) -> PyResult<Option<Self>> {
+        match seconds {
+            None => Ok(None),
+            Some(s) if s > 0.0 && s.is_finite() => Ok(Some(Ttl { seconds: s, expire_dependents })),
+            Some(s) => Err(PyValueError::new_err(format!(
+                "ttl must be a positive number of seconds, got {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// Deadlines of a cached value.
+#[derive(Clone, Copy, Default, Debug)]
+pub(crate) struct Expiry {
+    // The value is rebuilt once the clock reaches this
+    pub(crate) at: Option<f64>,
+    // Deadline passed on to the singletons built from the value
+    pub(crate) inherited: Option<f64>,
+}
+
+impl Expiry {
+    /// Expiry of a value produced at `now` by a provider with `ttl`, from
+    /// dependencies passing on the deadline `inherited`.
+    pub(crate) fn new(now: f64, ttl: Option<Ttl>, inherited: Option<f64>) -> Self {
+        let own = ttl.map(|t| now + t.seconds);
+        let passed = ttl.filter(|t| t.expire_dependents).and(own);
+        Expiry { at: earliest(own, inherited), inherited: earliest(passed, inherited) }
+    }
+
+    pub(crate) fn expired(&self, now: f64) -> bool {
+        self.at.is_some_and(|at| now >= at)
+    }
+}
+
+/// The earlier of two optional deadlines.
+pub(crate) fn earliest(a: Option<f64>, b: Option<f64>) -> Option<f64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
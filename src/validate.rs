@@ -0,0 +1,217 @@
+//! Whole-graph validation (`Container.validate()`).
+//!
+//! Unlike plan compilation, which stops at the first error of one root set,
+//! validation walks every provider entry (base registrations and active
+//! override layers) and reports every problem it finds at once.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use pyo3::prelude::*;
+
+use crate::{errors, Captive, ContainerInner};
+
+/// One wiring problem found by `Container.validate()`.
+#[pyclass(frozen, get_all, module = "_fastdi_core")]
+pub(crate) struct Problem {
+    /// `"missing"`, `"cycle"`, `"async_in_sync"` or `"scope"`
+    kind: String,
+    /// The key the problem is about: the missing dependency, the first cycle
+    /// member, the async provider, or the singleton with a captive dependency
+    key: String,
+    /// Keys leading to the problem; a cycle's path ends where it started
+    path: Vec<String>,
+    message: String,
+}
+
+#[pymethods]
+impl Problem {
+    fn __repr__(&self) -> String {
+        format!("Problem(kind={:?}, key={:?}, path={:?})", self.kind, self.key, self.path)
+    }
+}
+
+/// Result of `Container.validate()`: every problem found, in a stable order.
+#[pyclass(frozen, module = "_fastdi_core")]
+pub(crate) struct ValidationReport {
+    problems: Vec<Py<Problem>>,
+}
+
+#[pymethods]
+impl ValidationReport {
+    /// Whether no problem was found.
+    #[getter]
+    fn ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    #[getter]
+    fn problems(&self, py: Python<'_>) -> Vec<Py<Problem>> {
+        self.problems.iter().map(|p| p.clone_ref(py)).collect()
+    }
+
+    fn __len__(&self) -> usize {
+        self.problems.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("ValidationReport(ok={}, problems={})", self.ok(), self.problems.len())
+    }
+
+    /// One line per problem, e.g. for an assertion message.
+    fn __str__(&self) -> String {
+        if self.problems.is_empty() {
+            return "no problems found".to_string();
+        }
+        let lines: Vec<String> = self
+            .problems
+            .iter()
+            .map(|p| format!("{}: {}", p.get().kind, p.get().message))
+            .collect();
+        lines.join("\n")
+    }
+}
+
+impl ValidationReport {
+    pub(crate) fn new(py: Python<'_>, problems: Vec<Problem>) -> PyResult<Self> {
+        let problems = problems.into_iter().map(|p| Py::new(py, p)).collect::<PyResult<_>>()?;
+        Ok(Self { problems })
+    }
+}
+
+fn problem(kind: &str, key: &str, path: Vec<String>, message: String) -> Problem {
+    Problem { kind: kind.to_string(), key: key.to_string(), path, message }
+}
+
+impl ContainerInner {
+    /// Validate every provider entry; `sync_roots` are the keys resolved by
+    /// sync-only consumers (`compile()` without `allow_async`). Captive
+    /// dependencies are reported unless `captive` is `Ignore`.
+    pub(crate) fn validate(
+        &self,
+        sync_roots: &BTreeSet<String>,
+        captive: Captive,
+    ) -> Vec<Problem> {
+        let mut problems = Vec::new();
+
+        // Every entry, shadowed ones included; dependencies are looked up the
+        // way resolution would, topmost override layer first
+        let mut entries: Vec<_> = self.entries().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        let mut reported = HashSet::new();
+        for &(key, provider) in &entries {
+            for dep in self.dep_names(provider) {
+                if !reported.insert((key, dep)) {
+                    continue;
+                }
+                let path = vec![key.to_string(), dep.to_string()];
+                match self.get_key(dep) {
+                    None => {
+                        let message = errors::missing_message(dep, &path[..1]);
+                        problems.push(problem("missing", dep, path, message));
+                    }
+                    Some(d)
+                        if captive != Captive::Ignore
+                            && provider.is_singleton()
+                            && !d.is_singleton() =>
+                    {
+                        let message = errors::captive_message(key, dep, d.meta.lifetime.name());
+                        problems.push(problem("scope", key, path, message));
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+
+        let mut keys: Vec<&str> = entries.iter().map(|(k, _)| *k).collect();
+        keys.dedup();
+        self.find_cycles(&keys, &mut problems);
+        for root in sync_roots {
+            if self.get_key(root).is_none() {
+                let message = errors::missing_message(root, &[]);
+                problems.push(problem("missing", root, vec![root.clone()], message));
+                continue;
+            }
+            self.find_async(root, &mut problems);
+        }
+        problems
+    }
+
+    /// Report each cycle of the active graph once, starting at its smallest key.
+    fn find_cycles(&self, keys: &[&str], problems: &mut Vec<Problem>) {
+        // `false` while the key is on the DFS stack, `true` once finished
+        let mut state: HashMap<String, bool> = HashMap::new();
+        let mut seen_cycles: HashSet<Vec<String>> = HashSet::new();
+
+        fn visit(
+            me: &ContainerInner,
+            key: &str,
+            stack: &mut Vec<String>,
+            state: &mut HashMap<String, bool>,
+            seen_cycles: &mut HashSet<Vec<String>>,
+            problems: &mut Vec<Problem>,
+        ) {
+            match state.get(key) {
+                Some(true) => return,
+                Some(false) => {
+                    let start = stack.iter().position(|k| k == key).unwrap_or(0);
+                    let mut cycle = stack[start..].to_vec();
+                    let first = (0..cycle.len()).min_by_key(|&i| &cycle[i]).unwrap_or(0);
+                    cycle.rotate_left(first);
+                    if seen_cycles.insert(cycle.clone()) {
+                        let message = errors::cycle_message(&cycle[0], &cycle);
+                        let mut path = cycle.clone();
+                        path.push(cycle[0].clone());
+                        problems.push(problem("cycle", &cycle[0], path, message));
+                    }
+                    return;
+                }
+                None => {}
+            }
+            let Some(provider) = me.get_key(key) else { return };
+            state.insert(key.to_string(), false);
+            stack.push(key.to_string());
+            for dep in me.dep_names(provider) {
+                visit(me, dep, stack, state, seen_cycles, problems);
+            }
+            stack.pop();
+            state.insert(key.to_string(), true);
+        }
+
+        for key in keys {
+            visit(self, key, &mut Vec::new(), &mut state, &mut seen_cycles, problems);
+        }
+    }
+
+    /// Report async providers reachable from the sync root `root`.
+    fn find_async(&self, root: &str, problems: &mut Vec<Problem>) {
+        fn visit(
+            me: &ContainerInner,
+            root: &str,
+            key: &str,
+            path: &mut Vec<String>,
+            seen: &mut HashSet<String>,
+            problems: &mut Vec<Problem>,
+        ) {
+            if !seen.insert(key.to_string()) {
+                return;
+            }
+            let Some(provider) = me.get_key(key) else { return };
+            path.push(key.to_string());
+            if provider.meta.is_async {
+                let message = format!(
+                    "Provider for key '{}' is async but reachable from sync consumer of '{}' ({})",
+                    key,
+                    root,
+                    path.join(" -> ")
+                );
+                problems.push(problem("async_in_sync", key, path.clone(), message));
+            } else {
+                for dep in me.dep_names(provider) {
+                    visit(me, root, dep, path, seen, problems);
+                }
+            }
+            path.pop();
+        }
+
+        visit(self, root, root, &mut Vec::new(), &mut HashSet::new(), problems);
+    }
+}
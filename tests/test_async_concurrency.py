@@ -0,0 +1,104 @@
+import asyncio
+from typing import Annotated
+
+import pytest
+
+from fastdi import Container, Depends, ainject, provide
+
+
+@pytest.mark.asyncio
+async def test_independent_async_providers_run_concurrently():
+    c = Container()
+    running = 0
+    peak = 0
+
+    async def io(value: str) -> str:
+        nonlocal running, peak
+        running += 1
+        peak = max(peak, running)
+        await asyncio.sleep(0.05)
+        running -= 1
+        return value
+
+    @provide(c)
+    async def http_client():
+        return await io("http")
+
+    @provide(c)
+    async def db_pool():
+        return await io("db")
+
+    @provide(c)
+    async def cache():
+        return await io("cache")
+
+    @provide(c)
+    async def service(
+        h: Annotated[str, Depends(http_client)],
+        d: Annotated[str, Depends(db_pool)],
+        k: Annotated[str, Depends(cache)],
+    ):
+        return f"{h}+{d}+{k}"
+
+    @ainject(c)
+    async def handler(s: Annotated[str, Depends(service)], d: Annotated[str, Depends(db_pool)]):
+        return s, d
+
+    assert await handler() == ("http+db+cache", "db")
+    # All three providers of the level were in flight at once
+    assert peak == 3
+
+
+@pytest.mark.asyncio
+async def test_concurrent_level_computes_each_node_once():
+    c = Container()
+    calls = []
+
+    @provide(c, key="shared")
+    async def shared():
+        calls.append("shared")
+        await asyncio.sleep(0)
+        return 1
+
+    @provide(c, key="left")
+    async def left(x: Annotated[int, Depends("shared")]):
+        await asyncio.sleep(0)
+        return x + 1
+
+    @provide(c, key="right")
+    async def right(x: Annotated[int, Depends("shared")]):
+        await asyncio.sleep(0)
+        return x + 2
+
+    @provide(c, key="failing")
+    async def failing(x: Annotated[int, Depends("shared")]):
+        raise ValueError("nope")
+
+    assert await c.resolve_many_async(["left", "right"]) == [2, 3]
+    assert calls == ["shared"]
+
+    with pytest.raises(ValueError, match="nope"):
+        await c.resolve_many_async(["left", "failing"])
+
+
+@pytest.mark.asyncio
+async def test_failing_sibling_does_not_orphan_singleton():
+    c = Container()
+    log = []
+
+    @provide(c, key="pool", singleton=True)
+    async def pool():
+        log.append("start")
+        await asyncio.sleep(0.05)
+        log.append("done")
+        return "pool"
+
+    @provide(c, key="bad")
+    async def bad():
+        raise ValueError("bad")
+
+    with pytest.raises(ValueError, match="bad"):
+        await c.resolve_many_async(["pool", "bad"])
+    assert log == ["start", "done"]
+    assert await c.resolve_async("pool") == "pool"
+    assert log == ["start", "done"]
@@ -0,0 +1,71 @@
+import asyncio
+from typing import Annotated
+
+import pytest
+
+from fastdi import Container, Depends, ainject, make_key, provide
+
+
+@pytest.mark.asyncio
+async def test_core_async_resolution_caches_singletons():
+    c = Container()
+    calls = []
+
+    @provide(c, singleton=True)
+    async def pool():
+        calls.append("pool")
+        await asyncio.sleep(0.01)
+        return {"conn": 1}
+
+    @provide(c)
+    def settings():
+        return {"timeout": 5}
+
+    @provide(c)
+    async def client(p: Annotated[dict, Depends(pool)], s: Annotated[dict, Depends(settings)]):
+        await asyncio.sleep(0)
+        return (p["conn"], s["timeout"])
+
+    @ainject(c)
+    async def handler(v: Annotated[tuple, Depends(client)]):
+        return v
+
+    assert await handler() == (1, 5)
+    assert await c.resolve_async(make_key(client)) == (1, 5)
+    assert await c.resolve_many_async([make_key(pool), make_key(settings)]) == [{"conn": 1}, {"timeout": 5}]
+    assert calls == ["pool"]
+
+    # Only plans with request-scoped values or teardown use the task's implicit scope
+    assert len(c._task_scopes) == 0
+
+    @provide(c, key="session", scope="request")
+    def session():
+        return object()
+
+    first = await c.resolve_async("session")
+    assert await c.resolve_async("session") is first
+    assert len(c._task_scopes) == 1
+
+
+@pytest.mark.asyncio
+async def test_core_async_resolution_propagates_provider_errors():
+    c = Container()
+
+    @provide(c, key="broken")
+    async def broken():
+        await asyncio.sleep(0)
+        raise ValueError("boom")
+
+    @provide(c, key="user")
+    async def user(b: Annotated[int, Depends("broken")]):
+        return b
+
+    with pytest.raises(ValueError, match="boom"):
+        await c._core.resolve_async("user")
+
+    # The awaitable cannot be reused once it has finished.
+    awaitable = c._core.resolve_many_plan_async(["user"])
+    with pytest.raises(ValueError):
+        await awaitable
+    with pytest.raises(RuntimeError):
+        await awaitable
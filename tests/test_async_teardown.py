@@ -0,0 +1,117 @@
+import asyncio
+from typing import Annotated
+
+import pytest
+
+from fastdi import Container, Depends, ainject, provide
+
+
+class Session:
+    def __init__(self, events):
+        self.events = events
+
+    async def __aenter__(self):
+        self.events.append("session open")
+        return "session"
+
+    async def __aexit__(self, *exc):
+        await asyncio.sleep(0)
+        self.events.append("session closed")
+
+
+@pytest.mark.asyncio
+async def test_async_teardown_in_request_scope():
+    c = Container()
+    events = []
+
+    @provide(c, key="conn", scope="request")
+    async def conn():
+        events.append("conn open")
+        yield "conn"
+        await asyncio.sleep(0)
+        events.append("conn closed")
+
+    @provide(c, key="session", async_context_manager=True)
+    def session(_c: Annotated[str, Depends("conn")]):
+        return Session(events)
+
+    @ainject(c)
+    async def handler(s: Annotated[str, Depends("session")], cn: Annotated[str, Depends("conn")]):
+        return s, cn
+
+    async with c.request_scope() as scope:
+        assert await handler() == ("session", "conn")
+        assert events == ["conn open", "session open"]
+        with pytest.raises(RuntimeError, match="async"):
+            scope.close()
+    assert events[2:] == ["session closed", "conn closed"]
+
+    # Outside an explicit scope the task's implicit scope is closed when it ends
+    events.clear()
+    assert await asyncio.create_task(handler()) == ("session", "conn")
+    for _ in range(5):
+        await asyncio.sleep(0)
+    assert events == ["conn open", "session open", "session closed", "conn closed"]
+
+
+@pytest.mark.asyncio
+async def test_async_singleton_teardown_on_aclose():
+    c = Container()
+    closed = []
+
+    @provide(c, key="pool", singleton=True)
+    async def pool():
+        yield "pool"
+        closed.append("pool")
+
+    @provide(c, key="client", singleton=True)
+    async def client(p: Annotated[str, Depends("pool")]):
+        yield f"client({p})"
+        closed.append("client")
+
+    assert await c.resolve_async("client") == "client(pool)"
+    with pytest.raises(RuntimeError, match="aclose"):
+        c.close()
+
+    await c.aclose()
+    assert closed == ["client", "pool"]
+
+
+@pytest.mark.asyncio
+async def test_entered_sibling_of_failing_provider_is_torn_down():
+    c = Container()
+    events = []
+
+    @provide(c, key="session", singleton=True, async_context_manager=True)
+    def session():
+        return Session(events)
+
+    @provide(c, key="bad")
+    async def bad():
+        await asyncio.sleep(0)
+        raise ValueError("bad")
+
+    with pytest.raises(ValueError, match="bad"):
+        await c.resolve_many_async(["session", "bad"])
+    assert events == ["session open"]
+
+    await c.aclose()
+    assert events == ["session open", "session closed"]
+
+    # A cancelled level keeps the values that were already built
+    c = Container()
+    events.clear()
+    c.register("session", session, singleton=True, async_context_manager=True)
+
+    async def slow():
+        await asyncio.sleep(10)
+
+    c.register("slow", slow, singleton=False)
+    task = asyncio.ensure_future(c.resolve_many_async(["session", "slow"]))
+    for _ in range(5):
+        await asyncio.sleep(0)
+    task.cancel()
+    with pytest.raises(asyncio.CancelledError):
+        await task
+    await c.aclose()
+    assert events == ["session open", "session closed"]
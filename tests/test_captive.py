@@ -0,0 +1,72 @@
+import warnings
+from typing import Annotated
+
+import pytest
+
+from fastdi import CaptiveDependency, CaptiveDependencyWarning, Container, Depends, inject, provide
+
+
+def test_captive_dependency_rejected_in_error_mode():
+    c = Container(captive_dependencies="error")
+
+    @provide(c, key="session", scope="request")
+    def session():
+        return object()
+
+    @provide(c, key="pool", singleton=True)
+    def pool(s: Annotated[object, Depends("session")]):
+        return s
+
+    with pytest.raises(CaptiveDependency, match=r"'pool' \(singleton\) depends on 'session' \(request\)") as exc:
+
+        @inject(c)
+        def handler(p: Annotated[object, Depends("pool")]):
+            return p
+
+    assert exc.value.path == ["pool", "session"]
+    assert [(p.kind, p.key, p.path) for p in c.validate().problems] == [("scope", "pool", ["pool", "session"])]
+
+    # Resolution without a plan rejects the edge too, before building anything
+    with c.request_scope():
+        with pytest.raises(CaptiveDependency):
+            c.resolve("pool")
+        c.freeze()
+        with pytest.raises(CaptiveDependency):
+            c.resolve("pool")
+
+    lenient = Container(captive_dependencies="ignore")
+    lenient.register("session", session, singleton=False, scope="request")
+    lenient.register("pool", pool, singleton=True, dep_keys=["session"])
+    assert lenient.validate().ok
+    with lenient.request_scope():
+        assert lenient.resolve("pool") is not None
+
+
+def test_captive_dependency_warns_once_by_default():
+    c = Container()
+
+    @provide(c, key="session")
+    def session():
+        return object()
+
+    @provide(c, key="pool", singleton=True)
+    def pool(s: Annotated[object, Depends("session")]):
+        return s
+
+    with warnings.catch_warnings(record=True) as caught:
+        warnings.simplefilter("always")
+        c._core.compile(["pool"])
+        c._core.compile(["pool"])
+        c.resolve("pool")
+    captive = [w for w in caught if issubclass(w.category, CaptiveDependencyWarning)]
+    assert len(captive) == 1
+    assert "'session' (transient)" in str(captive[0].message)
+
+    # `resolve()` warns on its own as well
+    c = Container()
+    c.register("session", session, singleton=False)
+    c.register("pool", pool, singleton=True, dep_keys=["session"])
+    with warnings.catch_warnings(record=True) as caught:
+        warnings.simplefilter("always")
+        c.resolve("pool")
+    assert [w.category for w in caught] == [CaptiveDependencyWarning]
@@ -0,0 +1,75 @@
+from typing import Annotated
+
+import pytest
+
+from fastdi import Container, Depends, DisposeError, provide
+
+
+class Resource:
+    def __init__(self, name, log, fail=False):
+        self.name = name
+        self.log = log
+        self.fail = fail
+
+    def close(self):
+        self.log.append(self.name)
+        if self.fail:
+            raise ValueError(self.name)
+
+
+def test_close_disposes_singletons_in_reverse_order():
+    c = Container()
+    log = []
+
+    @provide(c, key="config", singleton=True)
+    def config():
+        return Resource("config", log, fail=True)
+
+    @provide(c, key="pool", singleton=True, dispose=lambda p: log.append(f"dispose {p.name}"))
+    def pool(_cfg: Annotated[Resource, Depends("config")]):
+        return Resource("pool", log)
+
+    @provide(c, key="client", singleton=True)
+    def client(_p: Annotated[Resource, Depends("pool")]):
+        return Resource("client", log, fail=True)
+
+    c.resolve("client")
+    with pytest.raises(DisposeError) as info:
+        c.close()
+    assert log == ["client", "dispose pool", "config"]
+    assert [str(e) for e in info.value.errors] == ["client", "config"]
+    assert isinstance(info.value.__cause__, ValueError)
+
+    assert c.closed
+    with pytest.raises(RuntimeError, match="closed"):
+        c.resolve("client")
+
+
+@pytest.mark.asyncio
+async def test_aclose_prefers_aclose_and_awaits_disposers():
+    c = Container()
+    log = []
+
+    class AsyncResource:
+        async def aclose(self):
+            log.append("aclose")
+
+        def close(self):
+            log.append("close")
+
+    async def dispose(value):
+        log.append(f"dispose {value}")
+
+    @provide(c, key="res", singleton=True)
+    def res():
+        return AsyncResource()
+
+    @provide(c, key="conn", singleton=True, dispose=dispose)
+    def conn(_r: Annotated[AsyncResource, Depends("res")]):
+        return "conn"
+
+    assert await c.resolve_async("conn") == "conn"
+    await c.aclose()
+    assert log == ["dispose conn", "aclose"]
+    with pytest.raises(RuntimeError, match="closed"):
+        await c.resolve_async("conn")
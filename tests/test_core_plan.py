@@ -0,0 +1,75 @@
+from typing import Annotated
+
+import pytest
+
+from fastdi import Container, Depends, provide
+
+
+def test_compiled_plan_order_and_invalidation():
+    c = Container()
+
+    @provide(c, key="db")
+    def db():
+        return "db"
+
+    @provide(c, key="repo")
+    def repo(d: Annotated[str, Depends("db")]):
+        return f"repo({d})"
+
+    plan = c._core.compile(["repo", "db"])
+    assert plan.keys == ["repo", "db"]
+    assert plan.order == ["db", "repo"]
+    assert plan.execute() == ["repo(db)", "db"]
+
+    # Re-registering changes the generation; the plan recompiles on next use.
+    c.register("db", lambda: "db2", singleton=False)
+    assert plan.execute() == ["repo(db2)", "db2"]
+
+    with c.override("repo", lambda: "fake"):
+        assert plan.order == ["repo", "db"]
+        assert plan.execute() == ["fake", "db2"]
+    assert plan.execute() == ["repo(db2)", "db2"]
+
+
+def test_compile_validates_graph():
+    c = Container()
+
+    @provide(c, key="a")
+    def a(b: Annotated[int, Depends("b")]):
+        return 1
+
+    @provide(c, key="b")
+    def b(a_: Annotated[int, Depends("a")]):
+        return 2
+
+    @provide(c, key="aio")
+    async def aio():
+        return 3
+
+    with pytest.raises(RuntimeError, match="cycle"):
+        c._core.compile(["a"])
+    with pytest.raises(KeyError):
+        c._core.compile(["missing"])
+    with pytest.raises(RuntimeError, match="async"):
+        c._core.compile(["aio"])
+
+    plan = c._core.compile(["aio"], allow_async=True)
+    with pytest.raises(RuntimeError, match="async"):
+        plan.execute()
+
+
+@pytest.mark.asyncio
+async def test_compiled_plan_execute_async():
+    c = Container()
+
+    @provide(c, key="n", singleton=True)
+    async def n():
+        return 20
+
+    @provide(c, key="double")
+    async def double(x: Annotated[int, Depends("n")]):
+        return x * 2
+
+    plan = c._core.compile(["double", "n"], allow_async=True)
+    assert await plan.execute_async() == [40, 20]
+    assert c._core.get_cached("n") == 20
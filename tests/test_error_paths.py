@@ -0,0 +1,66 @@
+import asyncio
+from typing import Annotated
+
+import pytest
+
+from fastdi import Container, Depends, ainject, inject, provide
+
+
+def test_missing_provider_reports_dependency_path():
+    c = Container()
+
+    @provide(c, key="service")
+    def service(r: Annotated[str, Depends("repo")]):
+        return r
+
+    @provide(c, key="repo")
+    def repo(d: Annotated[str, Depends("db")]):
+        return d
+
+    path = r"service -> repo -> db \(missing\)"
+    with pytest.raises(KeyError, match=path):
+        c.resolve("service")
+    with pytest.raises(KeyError, match=path):
+        asyncio.run(c.resolve_async("service"))
+    with pytest.raises(KeyError, match=path):
+
+        @inject(c)
+        def handler(s: Annotated[str, Depends("service")]):
+            return s
+
+    with pytest.raises(KeyError, match=path):
+
+        @ainject(c)
+        async def ahandler(s: Annotated[str, Depends("service")]):
+            return s
+
+
+def test_cycle_reports_members_and_path():
+    c = Container()
+
+    @provide(c, key="service")
+    def service(r: Annotated[str, Depends("repo")]):
+        return r
+
+    @provide(c, key="repo")
+    def repo(d: Annotated[str, Depends("db")]):
+        return d
+
+    @provide(c, key="db")
+    def db(s: Annotated[str, Depends("pool")]):
+        return s
+
+    @provide(c, key="pool")
+    def pool(r: Annotated[str, Depends("repo")]):
+        return r
+
+    expected = r"at key: repo \(repo -> db -> pool -> repo, reached via service\)"
+    with pytest.raises(RuntimeError, match=expected):
+        c.resolve("service")
+    with pytest.raises(RuntimeError, match=expected):
+        c._core.compile(["service"], allow_async=True)
+
+    # A provider depending on itself is a cycle of one
+    c.register("pool", lambda p: p, singleton=False, dep_keys=["pool"])
+    with pytest.raises(RuntimeError, match=r"at key: pool \(pool -> pool\)"):
+        c.resolve("pool")
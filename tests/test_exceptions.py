@@ -0,0 +1,70 @@
+from typing import Annotated
+
+import pytest
+
+from fastdi import (
+    AsyncProviderInSyncContext,
+    Container,
+    DependencyCycle,
+    Depends,
+    DisposeError,
+    FastDIError,
+    ProviderNotFound,
+    ScopeError,
+    provide,
+)
+
+
+def test_hierarchy_keeps_builtin_bases():
+    assert issubclass(ProviderNotFound, FastDIError) and issubclass(ProviderNotFound, KeyError)
+    for exc in (DependencyCycle, AsyncProviderInSyncContext, ScopeError, DisposeError):
+        assert issubclass(exc, FastDIError) and issubclass(exc, RuntimeError)
+
+
+def test_errors_carry_key_and_path():
+    c = Container()
+
+    @provide(c, key="repo")
+    def repo(d: Annotated[str, Depends("db")]):
+        return d
+
+    @provide(c, key="broken")
+    def broken():
+        return {}["nope"]
+
+    @provide(c, key="a")
+    def a(b: Annotated[int, Depends("b")]):
+        return b
+
+    @provide(c, key="b")
+    def b(a_: Annotated[int, Depends("a")]):
+        return a_
+
+    @provide(c, key="aio")
+    async def aio():
+        return 1
+
+    @provide(c, key="conn", scope="request")
+    def conn():
+        yield "conn"
+
+    with pytest.raises(ProviderNotFound) as missing:
+        c.resolve("repo")
+    assert (missing.value.key, missing.value.path) == ("db", ["repo", "db"])
+
+    # A KeyError raised by a provider is not mistaken for a missing provider
+    with pytest.raises(KeyError) as raised:
+        c.resolve("broken")
+    assert not isinstance(raised.value, FastDIError)
+
+    with pytest.raises(DependencyCycle) as cycle:
+        c._core.compile(["a"])
+    assert (cycle.value.key, cycle.value.path) == ("a", ["a", "b", "a"])
+
+    with pytest.raises(AsyncProviderInSyncContext) as in_sync:
+        c.resolve("aio")
+    assert in_sync.value.key == "aio"
+
+    with pytest.raises(ScopeError) as no_scope:
+        c.resolve("conn")
+    assert no_scope.value.key == "conn"
@@ -0,0 +1,96 @@
+import json
+from typing import Annotated
+
+import pytest
+
+from fastdi import Container, Depends, provide
+
+
+def test_export_graph_json_describes_nodes_and_edges():
+    c = Container()
+
+    @provide(c, key="db", singleton=True)
+    def db():
+        return "db"
+
+    @provide(c, key="repo")
+    async def repo(d: Annotated[str, Depends("db")], cfg: Annotated[dict, Depends("config")]):
+        return d
+
+    @provide(c, key="session", scope="request")
+    def session():
+        return object()
+
+    c.resolve("db")
+    with c.override("session", lambda: "fake"):
+        graph = json.loads(c.export_graph(format="json"))
+
+    nodes = {n["key"]: n for n in graph["nodes"]}
+    assert list(nodes) == ["config", "db", "repo", "session"]
+    assert nodes["db"] == {
+        "key": "db",
+        "scope": "singleton",
+        "async": False,
+        "overridden": False,
+        "cached": True,
+        "missing": False,
+    }
+    assert nodes["repo"]["async"] and not nodes["repo"]["cached"]
+    assert nodes["session"]["overridden"]
+    assert nodes["config"]["missing"] and nodes["config"]["scope"] is None
+    assert {(e["dependency"], e["dependent"]) for e in graph["edges"]} == {("db", "repo"), ("config", "repo")}
+    assert not json.loads(c.export_graph(format="json"))["nodes"][3]["overridden"]
+
+    # An override of a key nothing else provides shadows nothing
+    with c.override("config", lambda: {}):
+        nodes = {n["key"]: n for n in json.loads(c.export_graph(format="json"))["nodes"]}
+    assert not nodes["config"]["overridden"] and not nodes["config"]["missing"]
+
+
+def test_export_graph_skips_expired_values():
+    class FakeClock:
+        now = 0.0
+
+        def __call__(self):
+            return self.now
+
+    clock = FakeClock()
+    c = Container(clock=clock)
+
+    @provide(c, key="token", singleton=True, ttl=10)
+    def token():
+        return object()
+
+    c.resolve("token")
+    assert json.loads(c.export_graph(format="json"))["nodes"][0]["cached"]
+    clock.now = 10
+    assert not json.loads(c.export_graph(format="json"))["nodes"][0]["cached"]
+    assert "cached" not in c.export_graph()
+
+
+def test_export_graph_text_formats():
+    c = Container()
+    assert json.loads(c.export_graph(format="json")) == {"nodes": [], "edges": []}
+    assert c.export_graph() == "flowchart TD\n"
+
+    @provide(c, key="db", singleton=True)
+    def db():
+        return "db"
+
+    @provide(c, key="repo")
+    async def repo(d: Annotated[str, Depends("db")], cfg: Annotated[dict, Depends("config")]):
+        return d
+
+    mermaid = c.export_graph()
+    assert mermaid.startswith("flowchart TD\n")
+    assert '    n1["db<br/>singleton"]' in mermaid
+    assert "    n1 --> n2" in mermaid
+    assert "    class n0 missing" in mermaid
+
+    dot = c.export_graph(format="dot")
+    assert dot.startswith("digraph fastdi {")
+    assert '    "db" -> "repo";' in dot
+    assert '"repo" [label="repo\\ntransient, async"];' in dot
+
+    with pytest.raises(ValueError, match="Unknown graph format"):
+        c.export_graph(format="svg")  # type: ignore[arg-type]
@@ -0,0 +1,50 @@
+import sys
+import sysconfig
+import threading
+
+import pytest
+
+from fastdi import Container, provide
+
+
+@pytest.mark.skipif(not sysconfig.get_config_var("Py_GIL_DISABLED"), reason="requires free-threaded CPython")
+def test_import_keeps_gil_disabled():
+    import fastdi  # noqa: F401
+
+    assert not sys._is_gil_enabled()
+
+
+def test_parallel_resolution_with_concurrent_registration():
+    c = Container()
+
+    @provide(c, key="config", singleton=True)
+    def config():
+        return {"dsn": "sqlite://"}
+
+    c.register("repo", lambda cfg: ("repo", cfg["dsn"]), singleton=False, dep_keys=["config"])
+    expected = c.resolve("config")
+    barrier = threading.Barrier(8)
+    errors = []
+
+    def reader():
+        barrier.wait()
+        try:
+            for _ in range(2000):
+                assert c.resolve("config") is expected
+                assert c.resolve("repo") == ("repo", "sqlite://")
+        except Exception as exc:  # pragma: no cover - reported below
+            errors.append(exc)
+
+    def writer():
+        barrier.wait()
+        for i in range(200):
+            c.register(f"extra{i}", lambda i=i: i, singleton=True, dep_keys=[])
+
+    threads = [threading.Thread(target=reader) for _ in range(7)] + [threading.Thread(target=writer)]
+    for t in threads:
+        t.start()
+    for t in threads:
+        t.join(timeout=30)
+
+    assert errors == []
+    assert c.resolve("extra199") == 199
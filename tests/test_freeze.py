@@ -0,0 +1,98 @@
+import asyncio
+from typing import Annotated
+
+import pytest
+
+from fastdi import Container, Depends, ProviderNotFound, provide
+
+
+def test_frozen_container_resolves_and_rejects_changes():
+    c = Container()
+
+    @provide(c, key="db", singleton=True)
+    def db():
+        return object()
+
+    @provide(c, key="repo")
+    def repo(d: Annotated[object, Depends("db")]):
+        return ("repo", d)
+
+    c.freeze()
+    c.freeze()
+    assert c.frozen
+
+    db_value = c.resolve("db")
+    assert c.resolve("repo") == ("repo", db_value)
+    assert c.resolve_many(["db", "repo"]) == [db_value, ("repo", db_value)]
+    with pytest.raises(ProviderNotFound):
+        c.resolve("missing")
+
+    for change in (
+        lambda: c.register("db", object, singleton=True),
+        lambda: c.unregister("db"),
+        lambda: c.reset("db"),
+        lambda: c.override("db", object).__enter__(),
+    ):
+        with pytest.raises(RuntimeError, match="frozen"):
+            change()
+
+    copy = c.testing_copy()
+    assert not copy.frozen
+    with copy.override("db", lambda: "fake"):
+        assert copy.resolve("repo") == ("repo", "fake")
+    assert copy.resolve("db") is not db_value
+    assert c.resolve("db") is db_value
+
+
+def test_freeze_requires_a_complete_graph():
+    c = Container()
+
+    @provide(c, key="repo")
+    def repo(d: Annotated[object, Depends("db")]):
+        return d
+
+    with pytest.raises(ProviderNotFound, match="repo -> db"):
+        c.freeze()
+    assert not c.frozen
+
+
+def test_singletons_built_before_freezing_are_reused():
+    class FakeClock:
+        now = 0.0
+
+        def __call__(self):
+            return self.now
+
+    clock = FakeClock()
+    c = Container(clock=clock)
+    built: list[str] = []
+
+    @provide(c, key="pool", singleton=True, eager=True)
+    def pool():
+        built.append("pool")
+        return object()
+
+    @provide(c, key="token", singleton=True, eager=True, ttl=10)
+    def token():
+        built.append("token")
+        return object()
+
+    @provide(c, key="client", singleton=True)
+    async def client():
+        built.append("client")
+        return object()
+
+    c.warmup()
+    client_value = asyncio.run(c.resolve_async("client"))
+    pool_value, token_value = c.resolve("pool"), c.resolve("token")
+    c.freeze()
+
+    assert c.resolve("pool") is pool_value
+    assert c.resolve("client") is client_value
+    assert c.resolve("token") is token_value
+    assert built == ["pool", "token", "client"]
+
+    # Expiring singletons are not published and keep being rebuilt
+    clock.now = 10
+    assert c.resolve("token") is not token_value
+    assert built == ["pool", "token", "client", "token"]
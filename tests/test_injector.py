@@ -0,0 +1,98 @@
+import inspect
+from typing import Annotated
+
+import pytest
+
+from fastdi import Container, Depends, inject, inject_method, provide
+
+
+def test_injector_fills_missing_arguments():
+    c = Container()
+
+    @provide(c, key="a")
+    def a():
+        return "A"
+
+    @provide(c, key="b")
+    def b():
+        return "B"
+
+    @inject(c)
+    def handler(x: int, a: Annotated[str, Depends("a")], *, b: Annotated[str, Depends("b")], y: int = 0):
+        """Handler docs."""
+        return (x, a, b, y)
+
+    assert handler(1) == (1, "A", "B", 0)
+    assert handler(1, "a", y=2) == (1, "a", "B", 2)
+    assert handler(1, b="b") == (1, "A", "b", 0)
+    assert handler(x=1, a="a", b="b") == (1, "a", "b", 0)
+    with pytest.raises(TypeError):
+        handler()
+
+    assert handler.__name__ == "handler"
+    assert handler.__doc__ == "Handler docs."
+    assert list(inspect.signature(handler).parameters) == ["x", "a", "b", "y"]
+
+    @inject(c)
+    def positional(a: Annotated[str, Depends("a")], /, b: Annotated[str, Depends("b")]):
+        return a + b
+
+    assert positional() == "AB"
+    assert positional("a") == "aB"
+
+
+def test_partial_calls_follow_registry_changes():
+    c = Container()
+    calls = []
+
+    @provide(c, key="a")
+    def a():
+        calls.append("a")
+        return "A"
+
+    @provide(c, key="b")
+    def b():
+        calls.append("b")
+        return "B"
+
+    @inject(c)
+    def handler(a: Annotated[str, Depends("a")], b: Annotated[str, Depends("b")]):
+        return a + b
+
+    # Only the missing dependency is built
+    assert handler("a") == "aB"
+    assert handler(b="b") == "Ab"
+    assert calls == ["b", "a"]
+
+    c.register("b", lambda: "new B", singleton=False)
+    assert handler("a") == "anew B"
+    with c.override("a", lambda: "fake A"):
+        assert handler(b="b") == "fake Ab"
+    assert handler(b="b") == "Ab"
+
+
+def test_injector_binds_as_method():
+    c = Container()
+
+    @provide(c, key="a")
+    def a():
+        return "A"
+
+    @provide(c, key="b")
+    def b():
+        return "B"
+
+    class Service:
+        @inject_method(c)
+        def run(self, a: Annotated[str, Depends("a")], b: Annotated[str, Depends("b")]):
+            return (self, a, b)
+
+        @inject(c)
+        def plain(self, a: Annotated[str, Depends("a")]):
+            return (self, a)
+
+    s = Service()
+    assert s.run() == (s, "A", "B")
+    assert s.run(b="b") == (s, "A", "b")
+    assert s.plain() == (s, "A")
+    assert Service.plain(s, "a") == (s, "a")
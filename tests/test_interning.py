@@ -0,0 +1,42 @@
+from typing import Annotated
+
+import pytest
+
+from fastdi import Container, Depends, ProviderNotFound, provide
+
+
+def test_dependency_keys_are_not_registrations():
+    c = Container()
+
+    @provide(c, key="service")
+    def service(cfg: Annotated[dict, Depends("config")]):
+        return cfg
+
+    # "config" is known as a dependency only
+    assert c.keys() == ["service"]
+    with pytest.raises(ProviderNotFound):
+        c.resolve("config")
+    with pytest.raises(ProviderNotFound):
+        c.unregister("config")
+
+    @provide(c, key="config")
+    def config():
+        return {"env": "test"}
+
+    assert c.resolve("service") == {"env": "test"}
+
+
+def test_request_cache_is_per_key():
+    c = Container()
+
+    @provide(c, key="a", scope="request")
+    def a():
+        return object()
+
+    @provide(c, key="b", scope="request")
+    def b():
+        return object()
+
+    with c.request_scope():
+        assert c.resolve("a") is c.resolve("a")
+        assert c.resolve("a") is not c.resolve("b")
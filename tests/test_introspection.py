@@ -0,0 +1,79 @@
+from typing import Annotated
+
+import pytest
+
+from fastdi import Container, Depends, ProviderNotFound, provide
+
+
+def test_dependency_queries():
+    c = Container()
+
+    @provide(c, key="config")
+    def config():
+        return {}
+
+    @provide(c, key="db")
+    def db(cfg: Annotated[dict, Depends("config")]):
+        return cfg
+
+    @provide(c, key="repo")
+    def repo(d: Annotated[dict, Depends("db")], cfg: Annotated[dict, Depends("config")]):
+        return d
+
+    @provide(c, key="service")
+    def service(r: Annotated[dict, Depends("repo")], m: Annotated[str, Depends("metrics")]):
+        return r
+
+    assert c.keys() == ["config", "db", "repo", "service"]
+    assert c.dependencies("repo") == ["db", "config"]
+    assert c.dependencies("service", transitive=True) == ["config", "db", "metrics", "repo"]
+    assert c.dependents("config") == ["db", "repo"]
+    assert c.dependents("config", transitive=True) == ["db", "repo", "service"]
+    assert c.dependents("metrics") == ["service"]
+
+    with pytest.raises(ProviderNotFound):
+        c.dependencies("metrics")
+
+    # Transitive queries stop at cycles and leave out the queried key
+    c.register("metrics", lambda s: s, singleton=False, dep_keys=["service"])
+    assert c.dependencies("service", transitive=True) == ["config", "db", "metrics", "repo"]
+    assert c.dependents("service", transitive=True) == ["metrics"]
+
+
+def test_transitive_dependents_of_a_long_chain():
+    c = Container()
+    c.register("k0", lambda: 0, singleton=False)
+    for i in range(1, 2000):
+        c.register(f"k{i}", lambda v: v + 1, singleton=False, dep_keys=[f"k{i - 1}"])
+
+    assert c.dependents("k0") == ["k1"]
+    assert len(c.dependents("k0", transitive=True)) == 1999
+    assert c.dependents("k1998", transitive=True) == ["k1999"]
+
+
+def test_provider_layer_follows_overrides():
+    c = Container()
+
+    @provide(c, key="config")
+    def config():
+        return {}
+
+    @provide(c, key="db")
+    def db(cfg: Annotated[dict, Depends("config")]):
+        return cfg
+
+    @provide(c, key="repo")
+    def repo(d: Annotated[dict, Depends("db")], cfg: Annotated[dict, Depends("config")]):
+        return d
+
+    assert c.provider_layer("db") == 0
+    with c.override("db", lambda: {"fake": True}):
+        assert c.provider_layer("db") == 1
+        assert c.dependents("config") == ["repo"]
+        with c.override("metrics", lambda: "noop"):
+            assert c.provider_layer("metrics") == 2
+            assert "metrics" in c.keys()
+        assert c.provider_layer("db") == 1
+    assert c.provider_layer("db") == 0
+    with pytest.raises(ProviderNotFound):
+        c.provider_layer("metrics")
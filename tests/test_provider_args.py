@@ -0,0 +1,24 @@
+import asyncio
+
+from fastdi import Container
+
+
+def test_providers_receive_dependencies_in_order():
+    for arity in range(6):
+        c = Container()
+        for i in range(arity):
+            c.register(f"d{i}", lambda i=i: i, singleton=False)
+        deps = [f"d{i}" for i in range(arity)]
+        c.register("take", lambda *args: args, singleton=False, dep_keys=deps)
+
+        async def atake(*args):
+            return args
+
+        c.register("atake", atake, singleton=False, dep_keys=deps)
+        expected = tuple(range(arity))
+
+        assert c.resolve("take") == expected
+        assert c._core.compile(["take"]).execute() == [expected]
+        assert asyncio.run(c._core.resolve_async("atake")) == expected
+        c.freeze()
+        assert c.resolve("take") == expected
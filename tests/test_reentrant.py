@@ -0,0 +1,40 @@
+import threading
+from typing import Annotated
+
+from fastdi import Container, Depends, inject, provide
+
+
+def test_provider_can_resolve_from_same_container():
+    c = Container()
+
+    @provide(c, key="config", singleton=True)
+    def config():
+        return {"dsn": "sqlite://"}
+
+    @provide(c, key="factory")
+    def factory():
+        # Lazy lookup while the outer resolution is in progress
+        return lambda: c.resolve("config")["dsn"]
+
+    @provide(c, key="eager")
+    def eager():
+        return c.resolve("config")["dsn"].upper()
+
+    @inject(c)
+    def handler(f: Annotated[object, Depends("factory")], e: Annotated[str, Depends("eager")]):
+        return f(), e
+
+    assert c.resolve("eager") == "SQLITE://"
+    assert handler() == ("sqlite://", "SQLITE://")
+
+    # A provider blocked on another thread's resolution must not hold up the container
+    result = {}
+
+    @provide(c, key="cross_thread")
+    def cross_thread():
+        t = threading.Thread(target=lambda: result.update(value=c.resolve("config")))
+        t.start()
+        t.join(timeout=5)
+        return result.get("value")
+
+    assert c.resolve("cross_thread") == {"dsn": "sqlite://"}
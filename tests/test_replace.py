@@ -0,0 +1,70 @@
+from typing import Annotated
+
+import pytest
+
+from fastdi import Container, Depends, ProviderNotFound, provide
+
+
+def test_replace_invalidates_dependent_singletons():
+    closed: list[str] = []
+    c = Container()
+
+    @provide(c, key="config", singleton=True)
+    def config():
+        yield {"dsn": "a"}
+        closed.append("config")
+
+    @provide(c, key="pool", singleton=True)
+    def pool(cfg: Annotated[dict, Depends("config")]):
+        yield cfg["dsn"]
+        closed.append("pool")
+
+    @provide(c, key="clock", singleton=True)
+    def clock():
+        return object()
+
+    clock_value = c.resolve("clock")
+    assert c.resolve("pool") == "a"
+
+    c.replace("config", lambda: {"dsn": "b"}, singleton=True)
+    assert closed == ["pool", "config"]
+    assert c.resolve("pool") == "b"
+    assert c.resolve("clock") is clock_value
+
+    # A dependent shadowed by an override layer is invalidated too
+    with c.override("pool", lambda: "fake", singleton=True):
+        c.register("config", lambda: {"dsn": "c"}, singleton=True)
+    assert c.resolve("pool") == "c"
+
+    # Invalidation follows the new wiring once a dependent is replaced
+    c.replace("pool", object, singleton=True)
+    pool_value = c.resolve("pool")
+    c.replace("config", lambda: {"dsn": "d"}, singleton=True)
+    assert c.resolve("pool") is pool_value
+
+    with pytest.raises(ProviderNotFound):
+        c.replace("missing", lambda: None, singleton=False)
+
+
+def test_unregister_removes_provider_and_stale_values():
+    closed: list[str] = []
+    c = Container()
+
+    @provide(c, key="config", singleton=True)
+    def config():
+        yield {"dsn": "a"}
+        closed.append("config")
+
+    @provide(c, key="pool", singleton=True)
+    def pool(cfg: Annotated[dict, Depends("config")]):
+        yield cfg["dsn"]
+        closed.append("pool")
+
+    c.resolve("pool")
+    c.unregister("config")
+    assert closed == ["pool", "config"]
+    assert "config" not in c.keys()
+    with pytest.raises(ProviderNotFound, match="pool -> config"):
+        c.resolve("pool")
+    with pytest.raises(ProviderNotFound):
+        c.unregister("config")
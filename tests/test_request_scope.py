@@ -0,0 +1,59 @@
+import threading
+from typing import Annotated
+
+import pytest
+
+from fastdi import Container, Depends, inject, provide
+
+
+def test_sync_request_scope_caches_until_exit():
+    c = Container()
+
+    @provide(c, key="rid", scope="request")
+    def rid():
+        return object()
+
+    @inject(c)
+    def handler(r: Annotated[object, Depends("rid")]):
+        return r
+
+    # Outside any scope request providers are not cached.
+    assert c.resolve("rid") is not c.resolve("rid")
+
+    with c.request_scope() as scope:
+        first = handler()
+        assert handler() is first
+        assert c.resolve("rid") is first
+    assert scope.closed
+
+    with c.request_scope():
+        assert handler() is not first
+
+    with pytest.raises(RuntimeError, match="closed"):
+        with scope:
+            pass
+
+
+def test_request_scope_is_per_thread():
+    c = Container()
+
+    @provide(c, key="rid", scope="request")
+    def rid():
+        return object()
+
+    results = {}
+
+    def worker(name):
+        with c.request_scope():
+            results[name] = (c.resolve("rid"), c.resolve("rid"))
+
+    threads = [threading.Thread(target=worker, args=(n,)) for n in ("a", "b")]
+    for t in threads:
+        t.start()
+    for t in threads:
+        t.join()
+
+    a1, a2 = results["a"]
+    b1, b2 = results["b"]
+    assert a1 is a2 and b1 is b2
+    assert a1 is not b1
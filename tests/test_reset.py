@@ -0,0 +1,65 @@
+from typing import Annotated
+
+import pytest
+
+from fastdi import Container, Depends, ProviderNotFound, provide
+
+
+def test_reset_one_key_or_its_subtree():
+    closed: list[str] = []
+    c = Container()
+
+    @provide(c, key="config", singleton=True)
+    def config():
+        yield object()
+        closed.append("config")
+
+    @provide(c, key="pool", singleton=True)
+    def pool(cfg: Annotated[object, Depends("config")]):
+        yield (cfg, object())
+        closed.append("pool")
+
+    cfg, _ = pool_value = c.resolve("pool")
+
+    c.reset("config")
+    assert closed == ["config"]
+    assert c.resolve("pool") is pool_value
+    assert c.resolve("config") is not cfg
+
+    c.reset("config", cascade=True, dispose=False)
+    assert closed == ["config"]
+    assert c.resolve("pool") is not pool_value
+
+    # Cascading from a key with nothing cached still clears its dependents
+    c.reset("config", dispose=False)
+    pool_value = c.resolve("pool")
+    c.reset("config", cascade=True)
+    assert closed == ["config", "pool"]
+    assert c.resolve("pool") is not pool_value
+
+    with pytest.raises(ProviderNotFound):
+        c.reset("missing")
+
+
+def test_reset_all_covers_override_layers():
+    closed: list[str] = []
+    c = Container()
+
+    @provide(c, key="config", singleton=True)
+    def config():
+        yield object()
+        closed.append("config")
+
+    @provide(c, key="pool", singleton=True)
+    def pool(cfg: Annotated[object, Depends("config")]):
+        yield (cfg, object())
+        closed.append("pool")
+
+    pool_value = c.resolve("pool")
+    with c.override("config", object, singleton=True):
+        fake = c.resolve("config")
+        c.reset_all()
+        assert closed == ["pool", "config"]
+        assert c.resolve("config") is not fake
+    assert c.resolve("pool") is not pool_value
+    assert not c.closed
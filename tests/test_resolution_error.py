@@ -0,0 +1,86 @@
+import asyncio
+from typing import Annotated
+
+import pytest
+
+from fastdi import Container, Depends, ResolutionError, ainject, inject, provide
+
+
+def test_provider_failures_are_wrapped_when_enabled():
+    c = Container(wrap_errors=True)
+
+    @provide(c, key="db")
+    def db():
+        raise ConnectionError("db down")
+
+    @provide(c, key="repo")
+    def repo(d: Annotated[str, Depends("db")]):
+        return d
+
+    with pytest.raises(ResolutionError) as resolved:
+        c.resolve("repo")
+    assert (resolved.value.key, resolved.value.path) == ("db", ["repo", "db"])
+    assert isinstance(resolved.value.__cause__, ConnectionError)
+
+    @inject(c)
+    def handler(r: Annotated[str, Depends("repo")]):
+        return r
+
+    with pytest.raises(ResolutionError, match=r"repo -> db"):
+        handler()
+
+
+def test_failures_in_gathered_levels_are_attributed():
+    c = Container(wrap_errors=True)
+
+    @provide(c, key="cache")
+    async def cache():
+        await asyncio.sleep(0)
+        return "cache"
+
+    @provide(c, key="feed")
+    async def feed():
+        await asyncio.sleep(0)
+        raise TimeoutError("feed slow")
+
+    @provide(c, key="service")
+    async def service(r: Annotated[str, Depends("cache")], f: Annotated[str, Depends("feed")]):
+        return r + f
+
+    @ainject(c)
+    async def handler(s: Annotated[str, Depends("service")]):
+        return s
+
+    with pytest.raises(ResolutionError) as gathered:
+        asyncio.run(handler())
+    assert (gathered.value.key, gathered.value.path) == ("feed", ["service", "feed"])
+    assert isinstance(gathered.value.__cause__, TimeoutError)
+
+    # When several siblings fail, the first one of the level is reported
+    @provide(c, key="cache")
+    async def broken_cache():
+        await asyncio.sleep(0)
+        raise LookupError("cache miss")
+
+    with pytest.raises(ResolutionError) as first:
+        asyncio.run(handler())
+    assert (first.value.key, first.value.path) == ("cache", ["service", "cache"])
+    assert isinstance(first.value.__cause__, LookupError)
+
+
+def test_provider_failures_propagate_unchanged_by_default():
+    c = Container()
+
+    @provide(c, key="db")
+    def db():
+        raise ConnectionError("db down")
+
+    @provide(c, key="feed")
+    async def feed():
+        await asyncio.sleep(0)
+        raise TimeoutError("feed slow")
+
+    with pytest.raises(ConnectionError):
+        c.resolve("db")
+    with pytest.raises(TimeoutError):
+        asyncio.run(c.resolve_async("feed"))
@@ -0,0 +1,134 @@
+import asyncio
+import threading
+import time
+
+import pytest
+
+from fastdi import Container, DependencyCycle, provide
+
+
+def test_singleton_built_once_across_threads():
+    c = Container()
+    calls = []
+
+    @provide(c, key="pool", singleton=True)
+    def pool():
+        calls.append(threading.get_ident())
+        time.sleep(0.05)
+        return object()
+
+    barrier = threading.Barrier(8)
+    results = []
+
+    def worker():
+        barrier.wait()
+        results.append(c.resolve("pool"))
+
+    threads = [threading.Thread(target=worker) for _ in range(8)]
+    for t in threads:
+        t.start()
+    for t in threads:
+        t.join(timeout=5)
+
+    assert len(calls) == 1
+    assert len(results) == 8 and all(r is results[0] for r in results)
+
+
+@pytest.mark.asyncio
+async def test_async_singleton_built_once_across_tasks_and_retried_after_failure():
+    c = Container()
+    calls = []
+
+    @provide(c, key="client", singleton=True)
+    async def client():
+        calls.append(1)
+        await asyncio.sleep(0.01)
+        if len(calls) == 1:
+            raise ConnectionError("unavailable")
+        return object()
+
+    first = await asyncio.gather(*(c.resolve_async("client") for _ in range(5)), return_exceptions=True)
+    # The failed build is reported to its caller; waiters retry and share one value
+    assert sum(isinstance(r, ConnectionError) for r in first) == 1
+    values = [r for r in first if not isinstance(r, BaseException)]
+    assert len(calls) == 2
+    assert all(v is values[0] for v in values)
+    assert await c.resolve_async("client") is values[0]
+
+
+def test_singletons_waiting_for_each_other_across_threads_raise():
+    c = Container()
+    barrier = threading.Barrier(2)
+    met = set()
+
+    def meet(key):
+        # Both flights are held before either singleton resolves the other
+        if key not in met:
+            met.add(key)
+            barrier.wait(timeout=5)
+
+    @provide(c, key="a", singleton=True)
+    def a():
+        meet("a")
+        return c.resolve("b")
+
+    @provide(c, key="b", singleton=True)
+    def b():
+        meet("b")
+        return c.resolve("a")
+
+    errors = []
+
+    def worker(key):
+        try:
+            c.resolve(key)
+        except Exception as e:
+            errors.append(e)
+
+    threads = [threading.Thread(target=worker, args=(key,)) for key in ("a", "b")]
+    for t in threads:
+        t.start()
+    for t in threads:
+        t.join(timeout=5)
+
+    assert not any(t.is_alive() for t in threads)
+    assert len(errors) == 2 and all(isinstance(e, DependencyCycle) for e in errors)
+
+
+@pytest.mark.asyncio
+async def test_async_singletons_waiting_for_each_other_raise():
+    c = Container()
+
+    @provide(c, key="a", singleton=True)
+    async def a():
+        await asyncio.sleep(0)
+        return await c.resolve_async("b")
+
+    @provide(c, key="b", singleton=True)
+    async def b():
+        await asyncio.sleep(0)
+        return await c.resolve_async("a")
+
+    # From separate tasks, and from the gathered tasks of one level
+    tasks = asyncio.gather(c.resolve_async("a"), c.resolve_async("b"), return_exceptions=True)
+    results = await asyncio.wait_for(tasks, timeout=5)
+    assert all(isinstance(r, DependencyCycle) for r in results)
+    with pytest.raises(DependencyCycle):
+        await asyncio.wait_for(c.resolve_many_async(["a", "b"]), timeout=5)
+
+    # A singleton resolving itself through tasks it gathers
+    @provide(c, key="pool", singleton=True)
+    async def pool():
+        return await c.resolve_many_async(["conn", "cache"])
+
+    @provide(c, key="conn")
+    async def conn():
+        await asyncio.sleep(0)
+        return await c.resolve_async("pool")
+
+    @provide(c, key="cache")
+    async def cache():
+        return "cache"
+
+    with pytest.raises(DependencyCycle, match="'pool' is being initialized by a caller waiting"):
+        await asyncio.wait_for(c.resolve_async("pool"), timeout=5)
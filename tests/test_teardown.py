@@ -0,0 +1,69 @@
+from contextlib import contextmanager
+from typing import Annotated
+
+import pytest
+
+from fastdi import Container, Depends, inject, provide
+
+
+def test_request_scope_tears_down_in_reverse_order():
+    c = Container()
+    events = []
+
+    @provide(c, key="conn", scope="request")
+    def conn():
+        events.append("open conn")
+        yield "conn"
+        events.append("close conn")
+
+    @contextmanager
+    def session_cm(conn):
+        events.append("begin session")
+        yield f"session({conn})"
+        events.append("end session")
+
+    @provide(c, key="session", context_manager=True)
+    def session(conn: Annotated[str, Depends("conn")]):
+        return session_cm(conn)
+
+    @inject(c)
+    def handler(s: Annotated[str, Depends("session")]):
+        return s
+
+    with c.request_scope():
+        assert handler() == "session(conn)"
+        assert handler() == "session(conn)"
+        assert events == ["open conn", "begin session", "begin session"]
+
+    assert events[3:] == ["end session", "end session", "close conn"]
+
+    # Transient providers with teardown need an owner
+    with pytest.raises(RuntimeError, match="request scope"):
+        handler()
+
+
+def test_singleton_teardown_on_close_and_override_exit():
+    c = Container()
+    closed = []
+
+    @provide(c, key="pool", singleton=True)
+    def pool():
+        yield "pool"
+        closed.append("pool")
+
+    @provide(c, key="client", singleton=True)
+    def client(p: Annotated[str, Depends("pool")]):
+        yield f"client({p})"
+        closed.append("client")
+
+    def fake_client():
+        yield "fake"
+        closed.append("fake")
+
+    with c.override("client", fake_client, singleton=True):
+        assert c.resolve("client") == "fake"
+    assert closed == ["fake"]
+
+    assert c.resolve("client") == "client(pool)"
+    c.close()
+    assert closed == ["fake", "client", "pool"]
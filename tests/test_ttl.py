@@ -0,0 +1,78 @@
+import asyncio
+from typing import Annotated
+
+import pytest
+
+from fastdi import Container, Depends, provide
+
+
+class FakeClock:
+    def __init__(self) -> None:
+        self.now = 0.0
+
+    def __call__(self) -> float:
+        return self.now
+
+
+def test_expired_singletons_are_rebuilt():
+    clock = FakeClock()
+    c = Container(clock=clock)
+    closed: list[int] = []
+    built = iter(range(100))
+
+    @provide(c, key="token", singleton=True, ttl=10)
+    def token():
+        n = next(built)
+        yield n
+        closed.append(n)
+
+    @provide(c, key="client", singleton=True)
+    def client(t: Annotated[int, Depends("token")]):
+        return ("client", t)
+
+    @provide(c, key="feed", singleton=True, ttl=10)
+    async def feed():
+        return next(built)
+
+    assert c.resolve("token") == 0
+    assert asyncio.run(c.resolve_async("feed")) == 1
+    clock.now = 9.5
+    assert c.resolve("token") == 0
+    assert c.resolve("client") == ("client", 0)
+    assert asyncio.run(c.resolve_async("feed")) == 1
+
+    clock.now = 10
+    assert c.resolve("token") == 2
+    assert closed == [0]
+    # Dependents keep their value unless the provider expires them
+    assert c.resolve("client") == ("client", 0)
+    assert asyncio.run(c.resolve_async("feed")) == 3
+
+    with pytest.raises(ValueError, match="only supported for singletons"):
+        c.register("request", object, singleton=False, scope="request", ttl=1)
+
+
+def test_dependents_expire_with_their_dependency():
+    clock = FakeClock()
+    c = Container(clock=clock)
+    built = iter(range(100))
+
+    @provide(c, key="credentials", singleton=True, ttl=60, expire_dependents=True)
+    def credentials():
+        return next(built)
+
+    @provide(c, key="session", singleton=True)
+    def session(cred: Annotated[int, Depends("credentials")]):
+        return ("session", cred)
+
+    @provide(c, key="api", singleton=True)
+    async def api(s: Annotated[tuple, Depends("session")]):
+        return ("api", s)
+
+    assert asyncio.run(c.resolve_async("api")) == ("api", ("session", 0))
+    clock.now = 30
+    assert c.resolve("session") == ("session", 0)
+
+    clock.now = 61
+    assert asyncio.run(c.resolve_async("api")) == ("api", ("session", 1))
+    assert c.resolve("session") == ("session", 1)
@@ -0,0 +1,76 @@
+from typing import Annotated
+
+from fastdi import Container, Depends, inject, provide
+
+
+def test_validate_reports_every_problem():
+    c = Container()
+
+    @provide(c, key="repo")
+    def repo(d: Annotated[str, Depends("db")]):
+        return d
+
+    @provide(c, key="a")
+    def a(b: Annotated[int, Depends("b")]):
+        return b
+
+    @provide(c, key="b")
+    def b(a_: Annotated[int, Depends("a")]):
+        return a_
+
+    @provide(c, key="session", scope="request")
+    def session():
+        return object()
+
+    @provide(c, key="pool", singleton=True)
+    def pool(s: Annotated[object, Depends("session")]):
+        return s
+
+    c.register("client", lambda: "client", singleton=False)
+
+    @provide(c, key="service")
+    def service(x: Annotated[str, Depends("client")]):
+        return x
+
+    @inject(c)
+    def handler(s: Annotated[str, Depends("service")]):
+        return s
+
+    # Re-registered as async after the sync consumer was wired
+    @provide(c, key="client")
+    async def client():
+        return "client"
+
+    report = c.validate()
+    assert not report.ok
+    found = {(p.kind, p.key, tuple(p.path)) for p in report.problems}
+    assert found == {
+        ("missing", "db", ("repo", "db")),
+        ("cycle", "a", ("a", "b", "a")),
+        ("scope", "pool", ("pool", "session")),
+        ("async_in_sync", "client", ("service", "client")),
+    }
+    assert "repo -> db (missing)" in str(report)
+
+
+def test_validate_checks_override_layers():
+    c = Container()
+
+    @provide(c, key="db")
+    def db():
+        return "db"
+
+    @provide(c, key="repo")
+    def repo(d: Annotated[str, Depends("db")]):
+        return d
+
+    assert c.validate().ok
+    assert len(c.validate(sync_roots=["repo"])) == 0
+
+    def fake_db(cfg: Annotated[dict, Depends("config")]):
+        return cfg
+
+    with c.override("db", fake_db):
+        report = c.validate()
+        assert [(p.kind, p.key) for p in report.problems] == [("missing", "config")]
+    assert c.validate().ok
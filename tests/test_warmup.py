@@ -0,0 +1,61 @@
+import asyncio
+from typing import Annotated
+
+import pytest
+
+from fastdi import AsyncProviderInSyncContext, Container, Depends, provide
+
+
+def test_warmup_builds_eager_singletons_in_order():
+    c = Container()
+    built: list[str] = []
+
+    @provide(c, key="config", singleton=True)
+    def config():
+        built.append("config")
+        return {}
+
+    @provide(c, key="pool", singleton=True, eager=True)
+    def pool(cfg: Annotated[dict, Depends("config")]):
+        built.append("pool")
+        return object()
+
+    @provide(c, key="cache", singleton=True)
+    def cache():
+        built.append("cache")
+        return object()
+
+    c.warmup()
+    assert built == ["config", "pool"]
+    pool_value = c.resolve("pool")
+    assert built == ["config", "pool"]
+
+    c.warmup(["cache", "pool"])
+    assert built == ["config", "pool", "cache"]
+    assert c.resolve("pool") is pool_value
+
+    with pytest.raises(ValueError, match="only supported for singletons"):
+        c.register("request", object, singleton=False, eager=True)
+
+
+def test_awarmup_starts_async_singletons_concurrently():
+    c = Container()
+    running = 0
+    peak = 0
+
+    async def connect():
+        nonlocal running, peak
+        running += 1
+        peak = max(peak, running)
+        await asyncio.sleep(0.01)
+        running -= 1
+        return object()
+
+    c.register("db", connect, singleton=True, eager=True)
+    c.register("broker", connect, singleton=True, eager=True)
+
+    with pytest.raises(AsyncProviderInSyncContext):
+        c.warmup()
+    asyncio.run(c.awarmup())
+    assert peak == 2
+    assert c._core.get_cached("db") is not None and c._core.get_cached("broker") is not None